@@ -0,0 +1,212 @@
+//! Authorizer that can be any of the authorizers of this crate.
+use std::sync::Arc;
+
+use http::HeaderValue;
+
+#[cfg(feature = "client-credentials")]
+use super::BasicClientCredentialAuthorizer;
+use super::{
+    ApiKeyAuthorizer, Authorizer, AuthorizerHealth, BasicAuthAuthorizer, BearerTokenAuthorizer,
+    CredentialLocation, HealthStatus, RefreshFuture,
+};
+use crate::error::{Error, Result};
+
+/// One of the authorizers of this crate, selected at runtime.
+///
+/// Useful to store an authorizer picked by configuration without making the
+/// surrounding types generic. For custom authorizers, use
+/// `Arc<dyn Authorizer + Send + Sync>` or `Box<dyn Authorizer + Send + Sync>` instead.
+///
+/// [`AnyAuthorizer::None`] sends requests without authorization:
+/// [`HttpClient`](crate::HttpClient) and the tonic interceptor do not add a header,
+/// while [`Authorizer::authorization_header`] fails with [`Error::NoAuthorization`].
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`tonic::service::Interceptor`] is implemented for
+/// [`AnyAuthorizer`]. The interceptor does not insert the access token if the intercepted call
+/// already has an `Authorization` header.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum AnyAuthorizer {
+    /// See [`BearerTokenAuthorizer`].
+    Bearer(BearerTokenAuthorizer),
+    /// See [`BasicAuthAuthorizer`].
+    Basic(BasicAuthAuthorizer),
+    /// See [`ApiKeyAuthorizer`].
+    ApiKey(ApiKeyAuthorizer),
+    /// See [`BasicClientCredentialAuthorizer`].
+    #[cfg(feature = "client-credentials")]
+    ClientCredentials(BasicClientCredentialAuthorizer),
+    /// No authorization.
+    None,
+}
+
+impl From<BearerTokenAuthorizer> for AnyAuthorizer {
+    fn from(authorizer: BearerTokenAuthorizer) -> Self {
+        Self::Bearer(authorizer)
+    }
+}
+
+impl From<BasicAuthAuthorizer> for AnyAuthorizer {
+    fn from(authorizer: BasicAuthAuthorizer) -> Self {
+        Self::Basic(authorizer)
+    }
+}
+
+impl From<ApiKeyAuthorizer> for AnyAuthorizer {
+    fn from(authorizer: ApiKeyAuthorizer) -> Self {
+        Self::ApiKey(authorizer)
+    }
+}
+
+#[cfg(feature = "client-credentials")]
+impl From<BasicClientCredentialAuthorizer> for AnyAuthorizer {
+    fn from(authorizer: BasicClientCredentialAuthorizer) -> Self {
+        Self::ClientCredentials(authorizer)
+    }
+}
+
+impl Authorizer for AnyAuthorizer {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        match self {
+            Self::Bearer(authorizer) => authorizer.authorization_header(),
+            Self::Basic(authorizer) => authorizer.authorization_header(),
+            Self::ApiKey(authorizer) => authorizer.authorization_header(),
+            #[cfg(feature = "client-credentials")]
+            Self::ClientCredentials(authorizer) => authorizer.authorization_header(),
+            Self::None => Err(Error::NoAuthorization),
+        }
+    }
+
+    fn optional_authorization_header(&self) -> Result<Option<Arc<HeaderValue>>> {
+        match self {
+            Self::None => Ok(None),
+            _ => self.authorization_header().map(Some),
+        }
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        match self {
+            Self::ApiKey(authorizer) => authorizer.credential_location(),
+            _ => CredentialLocation::default(),
+        }
+    }
+
+    fn health(&self) -> AuthorizerHealth {
+        match self {
+            Self::Bearer(authorizer) => authorizer.health(),
+            Self::Basic(authorizer) => authorizer.health(),
+            Self::ApiKey(authorizer) => authorizer.health(),
+            #[cfg(feature = "client-credentials")]
+            Self::ClientCredentials(authorizer) => authorizer.health(),
+            Self::None => AuthorizerHealth::new(HealthStatus::Healthy),
+        }
+    }
+
+    fn refresh(&self) -> RefreshFuture<'_> {
+        match self {
+            #[cfg(feature = "client-credentials")]
+            Self::ClientCredentials(authorizer) => authorizer.refresh(),
+            _ => Box::pin(std::future::ready(Ok(()))),
+        }
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        match self {
+            Self::Bearer(authorizer) => authorizer.authorization_header_tonic(),
+            Self::Basic(authorizer) => authorizer.authorization_header_tonic(),
+            Self::ApiKey(authorizer) => authorizer.authorization_header_tonic(),
+            #[cfg(feature = "client-credentials")]
+            Self::ClientCredentials(authorizer) => authorizer.authorization_header_tonic(),
+            Self::None => Err(tonic::Status::unauthenticated(
+                Error::NoAuthorization.to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl tonic::service::Interceptor for AnyAuthorizer {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        if matches!(self, Self::None) {
+            return Ok(request);
+        }
+
+        super::intercept(self, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_any_authorizer() {
+        let bearer = AnyAuthorizer::from(BearerTokenAuthorizer::new("tok").unwrap());
+        assert_eq!(
+            bearer
+                .optional_authorization_header()
+                .unwrap()
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Bearer tok"
+        );
+
+        let none = AnyAuthorizer::None;
+        assert!(none.optional_authorization_header().unwrap().is_none());
+        assert!(matches!(
+            none.authorization_header(),
+            Err(Error::NoAuthorization)
+        ));
+        assert!(none.health().is_healthy());
+    }
+
+    #[test]
+    fn test_trait_objects() {
+        let authorizer: Arc<dyn Authorizer + Send + Sync> =
+            Arc::new(BearerTokenAuthorizer::new("tok").unwrap());
+        let client = crate::HttpClient::new(authorizer);
+        assert_eq!(
+            client.authorization_header().unwrap().to_str().unwrap(),
+            "Bearer tok"
+        );
+
+        let authorizer: Box<dyn Authorizer + Send + Sync> = Box::new(AnyAuthorizer::None);
+        assert!(
+            authorizer
+                .optional_authorization_header()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn test_interceptor() {
+        use tonic::service::Interceptor;
+
+        let mut none = AnyAuthorizer::None;
+        let request = none.call(tonic::Request::new(())).unwrap();
+        assert!(request.metadata().get("authorization").is_none());
+
+        let mut bearer = AnyAuthorizer::from(BearerTokenAuthorizer::new("tok").unwrap());
+        let request = bearer.call(tonic::Request::new(())).unwrap();
+        assert_eq!(
+            request
+                .metadata()
+                .get("authorization")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Bearer tok"
+        );
+    }
+}
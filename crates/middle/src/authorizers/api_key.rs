@@ -0,0 +1,157 @@
+//! API key authentication via a custom header or query parameter.
+use std::sync::Arc;
+
+use http::{HeaderName, HeaderValue};
+
+use super::{Authorizer, CredentialLocation, sensitive_header};
+use crate::error::{Error, Result};
+
+/// Where an [`ApiKeyAuthorizer`] sends the API key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyLocation {
+    /// A request header, for example `X-API-Key`.
+    Header,
+    /// A query parameter, for example `api_key`. Not supported for gRPC requests.
+    Query,
+}
+
+/// Authorizer that attaches a static API key to any request a client sends,
+/// either as a header or as a query parameter. The key is sent as is, without
+/// an auth-scheme.
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`Interceptor`](`tonic::service::Interceptor`) is implemented for
+/// [`ApiKeyAuthorizer`]. The interceptor does not insert the key if the intercepted call
+/// already has the header. Keys in query parameters cannot be sent with gRPC; the
+/// interceptor fails the call in that case.
+#[derive(Clone, veil::Redact)]
+pub struct ApiKeyAuthorizer {
+    location: CredentialLocation,
+    #[redact]
+    value: Arc<HeaderValue>,
+    #[cfg(feature = "tonic")]
+    #[redact]
+    metadata: tonic::metadata::MetadataValue<tonic::metadata::Ascii>,
+}
+
+impl ApiKeyAuthorizer {
+    /// Create a new authorizer sending `value` in the header or query parameter `name`.
+    ///
+    /// # Errors
+    /// - Fails with [`Error::InvalidHeaderName`] if `name` is not a valid header name
+    ///   and `location` is [`ApiKeyLocation::Header`].
+    /// - Fails with [`Error::InvalidHeaderValue`] if `value` is not a valid header value.
+    pub fn new(name: &str, value: &str, location: ApiKeyLocation) -> Result<Self> {
+        let location = match location {
+            ApiKeyLocation::Header => CredentialLocation::Header(
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| Error::InvalidHeaderName(name.to_string()))?,
+            ),
+            ApiKeyLocation::Query => CredentialLocation::Query(name.to_string()),
+        };
+        let built = sensitive_header(value)?;
+        Ok(Self {
+            location,
+            value: built.header,
+            #[cfg(feature = "tonic")]
+            metadata: built.metadata,
+        })
+    }
+
+    /// Create a new authorizer sending `value` in the header `name`.
+    ///
+    /// # Errors
+    /// See [`Self::new`].
+    pub fn header(name: &str, value: &str) -> Result<Self> {
+        Self::new(name, value, ApiKeyLocation::Header)
+    }
+
+    /// Create a new authorizer sending `value` in the query parameter `name`.
+    ///
+    /// # Errors
+    /// See [`Self::new`].
+    pub fn query(name: &str, value: &str) -> Result<Self> {
+        Self::new(name, value, ApiKeyLocation::Query)
+    }
+}
+
+impl Authorizer for ApiKeyAuthorizer {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        Ok(self.value.clone())
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        self.location.clone()
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        Ok(self.metadata.clone())
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl tonic::service::Interceptor for ApiKeyAuthorizer {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        super::intercept(self, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key() {
+        let authorizer = ApiKeyAuthorizer::header("X-API-Key", "my-key").unwrap();
+        assert_eq!(
+            authorizer.credential_location(),
+            CredentialLocation::Header(HeaderName::from_static("x-api-key"))
+        );
+        assert_eq!(
+            authorizer.authorization_header().unwrap().to_str().unwrap(),
+            "my-key"
+        );
+        assert!(!format!("{authorizer:?}").contains("my-key"));
+
+        assert!(matches!(
+            ApiKeyAuthorizer::header("invalid header", "my-key"),
+            Err(Error::InvalidHeaderName(_))
+        ));
+    }
+
+    #[cfg(feature = "tonic")]
+    mod tonic_tests {
+        use tonic::service::Interceptor;
+
+        use super::*;
+
+        #[test]
+        fn test_tonic_api_key_header() {
+            let mut interceptor = ApiKeyAuthorizer::header("X-API-Key", "my-key").unwrap();
+            let request = interceptor.call(tonic::Request::new(())).unwrap();
+            assert_eq!(
+                request
+                    .metadata()
+                    .get("x-api-key")
+                    .unwrap()
+                    .to_str()
+                    .unwrap(),
+                "my-key"
+            );
+            assert!(request.metadata().get("authorization").is_none());
+        }
+
+        #[test]
+        fn test_tonic_api_key_query_unsupported() {
+            let mut interceptor = ApiKeyAuthorizer::query("api_key", "my-key").unwrap();
+            assert!(interceptor.call(tonic::Request::new(())).is_err());
+        }
+    }
+}
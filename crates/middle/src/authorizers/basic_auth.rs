@@ -0,0 +1,102 @@
+//! HTTP Basic authentication.
+use std::sync::Arc;
+
+use base64::Engine;
+use http::HeaderValue;
+
+use super::{Authorizer, sensitive_header};
+use crate::error::{Error, Result};
+
+/// Authorizer that attaches HTTP Basic credentials (RFC 7617) to any request a
+/// client sends, as `Authorization: Basic <base64(username:password)>`.
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`Interceptor`](`tonic::service::Interceptor`) is implemented for
+/// [`BasicAuthAuthorizer`]. The interceptor does not insert the credentials if the intercepted call
+/// already has an `Authorization` header.
+#[derive(Clone, veil::Redact)]
+pub struct BasicAuthAuthorizer {
+    username: String,
+    #[redact]
+    authorization_header: Arc<HeaderValue>,
+    #[cfg(feature = "tonic")]
+    #[redact]
+    authorization_metadata: tonic::metadata::MetadataValue<tonic::metadata::Ascii>,
+}
+
+impl BasicAuthAuthorizer {
+    /// Create a new authorizer with the given credentials.
+    ///
+    /// # Errors
+    /// - Fails with [`Error::InvalidConfig`] if the username contains a colon, which cannot
+    ///   be represented in Basic credentials.
+    /// - Fails with [`Error::InvalidHeaderValue`] if the credentials are not ASCII.
+    pub fn new(username: &str, password: &str) -> Result<Self> {
+        if username.contains(':') {
+            return Err(Error::InvalidConfig(
+                "Basic auth username must not contain ':'".to_string(),
+            ));
+        }
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+        let built = sensitive_header(&format!("Basic {credentials}"))?;
+        Ok(Self {
+            username: username.to_string(),
+            authorization_header: built.header,
+            #[cfg(feature = "tonic")]
+            authorization_metadata: built.metadata,
+        })
+    }
+
+    /// The username of the credentials.
+    #[must_use]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+impl Authorizer for BasicAuthAuthorizer {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        Ok(self.authorization_header.clone())
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        Ok(self.authorization_metadata.clone())
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl tonic::service::Interceptor for BasicAuthAuthorizer {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        super::intercept(self, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_auth_header() {
+        let authorizer = BasicAuthAuthorizer::new("Aladdin", "open sesame").unwrap();
+        let header = authorizer.authorization_header().unwrap();
+        assert_eq!(
+            header.to_str().unwrap(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+        assert!(header.is_sensitive());
+        assert!(!format!("{authorizer:?}").contains("QWxh"));
+
+        assert!(matches!(
+            BasicAuthAuthorizer::new("user:name", "password"),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
@@ -63,15 +63,7 @@ impl Interceptor for BearerTokenAuthorizer {
         &mut self,
         request: tonic::Request<()>,
     ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
-        let mut request = request;
-        let metadata = request.metadata_mut();
-        if !metadata.contains_key(http::header::AUTHORIZATION.as_str()) {
-            metadata.insert(
-                http::header::AUTHORIZATION.as_str(),
-                self.authorization_header_tonic()?,
-            );
-        }
-        Ok(request)
+        super::intercept(self, request)
     }
 }
 
@@ -79,6 +71,14 @@ impl Interceptor for BearerTokenAuthorizer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_health() {
+        let authorizer = BearerTokenAuthorizer::new("my-token").unwrap();
+        let health = authorizer.health();
+        assert!(health.is_healthy());
+        assert!(health.next_refresh.is_none());
+    }
+
     #[cfg(feature = "tonic")]
     mod tonic_tests {
         use tonic::service::Interceptor;
@@ -0,0 +1,630 @@
+//! Synchronous client credentials flow, for applications without an async runtime.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex, RwLock},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime},
+};
+
+use http::HeaderValue;
+use oauth2::{
+    ClientId, ClientSecret, EndpointNotSet, EndpointSet, Scope, TokenResponse, TokenUrl,
+    basic::{BasicClient, BasicTokenResponse},
+};
+
+use super::{Authorizer, AuthorizerHealth, HealthStatus};
+use crate::error::{Error, Result};
+
+/// Minimum delay between refresh attempts, see the async [`ClientCredentialAuthorizer`](crate::ClientCredentialAuthorizer).
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+type TokenClient =
+    BasicClient<EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>;
+
+/// Authenticate with an `OAuth2` server using client credentials, without an async runtime.
+///
+/// Blocking counterpart of [`ClientCredentialAuthorizer`](crate::ClientCredentialAuthorizer):
+/// tokens are fetched with `reqwest::blocking` and refreshed before expiry on a
+/// background OS thread. The thread is stopped when the last handle to the
+/// authorizer is dropped.
+///
+/// A refresh can be triggered manually with [`BlockingClientCredentialAuthorizer::refresh_now`].
+/// [`Authorizer::health`] reports whether a valid token is available.
+///
+/// Uses `Arc` internally for cheap cloning.
+///
+/// Note that `reqwest::blocking` must not be used within an async runtime; use
+/// [`ClientCredentialAuthorizer`](crate::ClientCredentialAuthorizer) there instead.
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`tonic::service::Interceptor`] is implemented for
+/// [`BlockingClientCredentialAuthorizer`]. The interceptor does not insert the access token if the
+/// intercepted call already has an `Authorization` header.
+#[derive(Debug, Clone)]
+pub struct BlockingClientCredentialAuthorizer {
+    inner: Arc<Inner>,
+    refresh_thread: Option<Arc<RefreshThread>>,
+}
+
+/// Handle to the background refresh thread of a [`BlockingClientCredentialAuthorizer`].
+/// The thread is stopped when the last handle is dropped.
+#[derive(Debug)]
+pub struct RefreshThread {
+    inner: Arc<Inner>,
+    thread: JoinHandle<()>,
+}
+
+impl RefreshThread {
+    /// Returns `true` if the refresh thread has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+impl Drop for RefreshThread {
+    fn drop(&mut self) {
+        tracing::debug!("Stopping credential refresh thread.");
+        self.inner.signal(|signal| signal.stopped = true);
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    oauth2_client: TokenClient,
+    http_client: reqwest::blocking::Client,
+    scopes: Vec<Scope>,
+    extra_params: HashMap<String, String>,
+    max_retries: u32,
+    retry_interval: Duration,
+    tolerance: Duration,
+    token: RwLock<Result<Token>>,
+    refresh_state: Mutex<RefreshState>,
+    // Serializes token requests of the refresh thread and `refresh_now`.
+    refresh_lock: Mutex<()>,
+    signal: Mutex<Signal>,
+    wakeup: Condvar,
+}
+
+/// Bookkeeping of the refresh thread, reported by [`Authorizer::health`].
+#[derive(Debug)]
+struct RefreshState {
+    consecutive_failures: u32,
+    last_error: Option<Error>,
+    last_success: Option<SystemTime>,
+    next_refresh: Option<Instant>,
+}
+
+/// Wakes the refresh thread before its next scheduled refresh.
+#[derive(Debug, Default)]
+struct Signal {
+    stopped: bool,
+    reschedule: bool,
+}
+
+#[derive(veil::Redact, Clone)]
+struct Token {
+    #[redact]
+    header: Arc<HeaderValue>,
+    #[cfg(feature = "tonic")]
+    #[redact]
+    metadata: tonic::metadata::MetadataValue<tonic::metadata::Ascii>,
+    expiry: Option<Instant>,
+}
+
+impl Token {
+    fn try_from_tr(tr: &BasicTokenResponse) -> Result<Self> {
+        let built = super::bearer_header(tr.access_token().secret())?;
+        Ok(Self {
+            header: built.header,
+            #[cfg(feature = "tonic")]
+            metadata: built.metadata,
+            expiry: tr.expires_in().map(|e| Instant::now() + e),
+        })
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expiry.is_some_and(|expiry| Instant::now() >= expiry)
+    }
+}
+
+impl BlockingClientCredentialAuthorizer {
+    /// Create a new [`BlockingClientCredentialAuthorizerBuilder`].
+    #[must_use]
+    pub fn builder(
+        client_id: &str,
+        client_secret: &str,
+        token_url: url::Url,
+    ) -> BlockingClientCredentialAuthorizerBuilder {
+        BlockingClientCredentialAuthorizerBuilder::new(client_id, client_secret, token_url)
+    }
+
+    /// Get a reference to the refresh thread.
+    #[must_use]
+    pub fn refresh_thread(&self) -> Option<&RefreshThread> {
+        self.refresh_thread.as_deref()
+    }
+
+    /// Fetch a new token immediately instead of waiting for the scheduled refresh.
+    /// Blocks until the token request completes. After a successful refresh, the
+    /// refresh thread is rescheduled based on the expiry of the new token.
+    ///
+    /// A failure does not discard a cached token that is still valid, and the
+    /// refresh thread keeps its schedule.
+    ///
+    /// # Errors
+    /// Returns an error if fetching the token fails after all retries.
+    pub fn refresh_now(&self) -> Result<()> {
+        self.inner.refresh_token()?;
+        self.inner.signal(|signal| signal.reschedule = true);
+        Ok(())
+    }
+}
+
+impl Inner {
+    fn signal(&self, update: impl FnOnce(&mut Signal)) {
+        update(&mut self.signal.lock().expect("Non-poisoned lock"));
+        self.wakeup.notify_all();
+    }
+
+    /// Request a new token from the token endpoint, retrying up to `max_retries` times.
+    fn request_new_token(&self) -> Result<BasicTokenResponse> {
+        let mut counter = 0;
+        loop {
+            counter += 1;
+
+            let mut request = self.oauth2_client.exchange_client_credentials();
+            for scope in &self.scopes {
+                request = request.add_scope(scope.clone());
+            }
+            for (name, value) in &self.extra_params {
+                request = request.add_extra_param(name, value);
+            }
+
+            match request.request(&self.http_client) {
+                Ok(response) => {
+                    tracing::debug!(
+                        "Successfully refreshed token for client `{}`. Token expires in {:?}s",
+                        self.oauth2_client.client_id().as_str(),
+                        response.expires_in().map(|d| d.as_secs())
+                    );
+                    return Ok(response);
+                }
+                Err(e) => {
+                    if counter > self.max_retries {
+                        tracing::error!("Failed to fetch token after {} retries: {e}", counter);
+                        return Err(e.into());
+                    }
+                    tracing::debug!(
+                        "Failed to fetch token: {e}. Retrying in {}ms",
+                        self.retry_interval.as_millis()
+                    );
+                    std::thread::sleep(self.retry_interval);
+                }
+            }
+        }
+    }
+
+    /// Fetch a new token and record the result. A failure keeps a cached token
+    /// that has not yet expired.
+    fn refresh_token(&self) -> Result<()> {
+        let _refresh_guard = self.refresh_lock.lock().expect("Non-poisoned lock");
+        let result = self
+            .request_new_token()
+            .and_then(|tr| Token::try_from_tr(&tr));
+
+        let mut state = self.refresh_state.lock().expect("Non-poisoned lock");
+        match result {
+            Ok(token) => {
+                *self.token.write().expect("Non-poisoned lock") = Ok(token);
+                state.consecutive_failures = 0;
+                state.last_error = None;
+                state.last_success = Some(SystemTime::now());
+                Ok(())
+            }
+            Err(e) => {
+                let mut token = self.token.write().expect("Non-poisoned lock");
+                if !matches!(&*token, Ok(token) if !token.is_expired()) {
+                    *token = Err(e.clone());
+                }
+                state.consecutive_failures += 1;
+                state.last_error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// Time until the next refresh, or `None` if the token never expires.
+    fn next_refresh_in(&self) -> Option<Duration> {
+        let token = self.token.read().expect("Non-poisoned lock");
+        let Ok(token) = &*token else {
+            return Some(self.tolerance.max(MIN_REFRESH_INTERVAL));
+        };
+        let expires_in = token.expiry?.saturating_duration_since(Instant::now());
+        let next_refresh = if expires_in < self.tolerance {
+            expires_in / 2
+        } else {
+            expires_in.saturating_sub(self.tolerance)
+        };
+        Some(next_refresh.max(MIN_REFRESH_INTERVAL))
+    }
+}
+
+/// Body of the refresh thread: sleeps until the next refresh is due, or until it
+/// is stopped or rescheduled.
+fn refresh_loop(inner: &Inner) {
+    loop {
+        let sleep_duration = inner.next_refresh_in();
+        inner
+            .refresh_state
+            .lock()
+            .expect("Non-poisoned lock")
+            .next_refresh = sleep_duration.map(|d| Instant::now() + d);
+        let Some(sleep_duration) = sleep_duration else {
+            tracing::debug!("Token does not expire. Stopping refresh thread.");
+            return;
+        };
+
+        tracing::trace!("Sleeping for {}s", sleep_duration.as_secs());
+        let signal = inner.signal.lock().expect("Non-poisoned lock");
+        let (mut signal, timeout) = inner
+            .wakeup
+            .wait_timeout_while(signal, sleep_duration, |signal| {
+                !signal.stopped && !signal.reschedule
+            })
+            .expect("Non-poisoned lock");
+        if signal.stopped {
+            return;
+        }
+        if !timeout.timed_out() {
+            tracing::trace!("Token was refreshed manually. Rescheduling refresh.");
+            signal.reschedule = false;
+            continue;
+        }
+        drop(signal);
+
+        tracing::trace!("Refreshing token");
+        inner.refresh_token().ok();
+    }
+}
+
+impl Authorizer for BlockingClientCredentialAuthorizer {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        match &*self.inner.token.read().expect("Non-poisoned lock") {
+            Ok(token) if token.is_expired() => Err(Error::TokenExpired),
+            Ok(token) => Ok(token.header.clone()),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    fn health(&self) -> AuthorizerHealth {
+        let token_status = self.authorization_header().map(|_| ());
+        let state = self.inner.refresh_state.lock().expect("Non-poisoned lock");
+
+        let status = match (token_status, &state.last_error) {
+            (Ok(()), None) => HealthStatus::Healthy,
+            (Ok(()), Some(last_error)) => HealthStatus::Degraded {
+                last_error: last_error.clone(),
+            },
+            (Err(error), _) => HealthStatus::Unhealthy { error },
+        };
+
+        let mut health = AuthorizerHealth::new(status);
+        health.last_refresh = state.last_success;
+        health.consecutive_failures = state.consecutive_failures;
+        health.next_refresh = state.next_refresh;
+        health
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        match &*self.inner.token.read().expect("Non-poisoned lock") {
+            Ok(token) if token.is_expired() => Err(tonic::Status::unauthenticated(
+                Error::TokenExpired.to_string(),
+            )),
+            Ok(token) => Ok(token.metadata.clone()),
+            Err(e) => Err(tonic::Status::unauthenticated(e.to_string())),
+        }
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl tonic::service::Interceptor for BlockingClientCredentialAuthorizer {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        super::intercept(self, request)
+    }
+}
+
+/// Builder for [`BlockingClientCredentialAuthorizer`].
+///
+/// The following configurations are available:
+/// * `max_retries`: Number of consecutive retries for token requests. Default is 3.
+/// * `retry_interval`: Interval between consecutive retries. Default is 10ms.
+/// * `http_client`: Custom `reqwest::blocking::Client` to use for token requests. Default is a client with redirects disabled.
+/// * `scopes`: Scopes to request in the token. Empty by default.
+/// * `extra_params`: Extra parameters to include in the token request. Empty by default.
+/// * `enable_refresh`: Enable automatic token refresh. Default is `true`.
+/// * `refresh_tolerance`: Time before expiry at which the token is refreshed. Default is 30 seconds.
+#[derive(Debug, Clone)]
+pub struct BlockingClientCredentialAuthorizerBuilder {
+    oauth2_client: TokenClient,
+    max_retries: u32,
+    retry_interval: Duration,
+    http_client: Option<reqwest::blocking::Client>,
+    scopes: Vec<Scope>,
+    extra_params: HashMap<String, String>,
+    enable_refresh: bool,
+    refresh_tolerance: Duration,
+}
+
+impl BlockingClientCredentialAuthorizerBuilder {
+    /// Create a new builder from a client id, client secret and token url.
+    /// Initializes with 3 retries and a retry interval of 10ms.
+    #[must_use]
+    pub fn new(client_id: &str, client_secret: &str, token_url: url::Url) -> Self {
+        let oauth2_client = BasicClient::new(ClientId::new(client_id.to_string()))
+            .set_client_secret(ClientSecret::new(client_secret.to_string()))
+            .set_token_uri(TokenUrl::from_url(token_url));
+
+        Self {
+            oauth2_client,
+            max_retries: 3,
+            retry_interval: Duration::from_millis(10),
+            http_client: None,
+            scopes: Vec::new(),
+            extra_params: HashMap::new(),
+            enable_refresh: true,
+            refresh_tolerance: Duration::from_secs(30),
+        }
+    }
+
+    /// Optionally specify the `reqwest::blocking::Client` to use for token requests.
+    /// When setting a custom client, please make sure to set the `redirect` policy to `Policy::none()`
+    /// to prevent SSRF vulnerabilities.
+    #[must_use]
+    pub fn set_http_client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Set the maximum number of retries for token requests.
+    #[must_use]
+    pub fn set_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the interval between retries for token requests.
+    #[must_use]
+    pub fn set_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Add a scope to request in the token.
+    #[must_use]
+    pub fn add_scope(mut self, scope: &str) -> Self {
+        self.scopes.push(Scope::new(scope.to_string()));
+        self
+    }
+
+    /// Add multiple scopes to request in the token.
+    #[must_use]
+    pub fn add_scopes<I>(mut self, scopes: &[I]) -> Self
+    where
+        I: AsRef<str>,
+    {
+        self.scopes
+            .extend(scopes.iter().map(|s| Scope::new(s.as_ref().to_string())));
+        self
+    }
+
+    /// Add an extra parameter to include in the token request.
+    #[must_use]
+    pub fn add_extra_param(mut self, name: &str, value: &str) -> Self {
+        self.extra_params
+            .insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Disable the background refresh thread.
+    #[must_use]
+    pub fn disable_refresh(mut self) -> Self {
+        self.enable_refresh = false;
+        self
+    }
+
+    /// Set the time before expiry at which the token is refreshed.
+    #[must_use]
+    pub fn refresh_tolerance(mut self, tolerance: Duration) -> Self {
+        self.refresh_tolerance = tolerance;
+        self
+    }
+
+    /// Build the [`BlockingClientCredentialAuthorizer`], fetching the initial token.
+    /// Blocks until the initial token is available.
+    ///
+    /// # Errors
+    /// Returns an error if the initial token cannot be fetched.
+    ///
+    /// # Panics
+    /// Panics if [`Self::set_http_client`] was not called and the default client cannot be
+    /// created, if called within an async runtime, or if the refresh thread cannot be spawned.
+    pub fn build(self) -> Result<BlockingClientCredentialAuthorizer> {
+        let http_client = self.http_client.unwrap_or_else(|| {
+            reqwest::blocking::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("Failed to create reqwest client")
+        });
+
+        let mut inner = Inner {
+            oauth2_client: self.oauth2_client,
+            http_client,
+            scopes: self.scopes,
+            extra_params: self.extra_params,
+            max_retries: self.max_retries,
+            retry_interval: self.retry_interval,
+            tolerance: self.refresh_tolerance,
+            token: RwLock::new(Err(Error::TokenExpired)),
+            refresh_state: Mutex::new(RefreshState {
+                consecutive_failures: 0,
+                last_error: None,
+                last_success: Some(SystemTime::now()),
+                next_refresh: None,
+            }),
+            refresh_lock: Mutex::new(()),
+            signal: Mutex::new(Signal::default()),
+            wakeup: Condvar::new(),
+        };
+
+        let tr = inner.request_new_token()?;
+        *inner.token.get_mut().expect("Non-poisoned lock") = Token::try_from_tr(&tr);
+        let inner = Arc::new(inner);
+
+        let refresh_thread = if self.enable_refresh && tr.expires_in().is_some() {
+            tracing::debug!(
+                "Starting refresh thread to refresh tokens for client `{}` before expiry.",
+                inner.oauth2_client.client_id().as_str()
+            );
+            let inner_cloned = inner.clone();
+            let thread = std::thread::Builder::new()
+                .name("middle-token-refresh".to_string())
+                .spawn(move || refresh_loop(&inner_cloned))
+                .expect("Failed to spawn refresh thread");
+            Some(Arc::new(RefreshThread {
+                inner: inner.clone(),
+                thread,
+            }))
+        } else {
+            None
+        };
+
+        Ok(BlockingClientCredentialAuthorizer {
+            inner,
+            refresh_thread,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::CONTENT_TYPE;
+
+    use super::*;
+
+    fn token_mock(server: &mut mockito::Server, token: &str, expect: usize) -> mockito::Mock {
+        server
+            .mock("POST", "/token")
+            .match_body(mockito::Matcher::UrlEncoded(
+                "grant_type".to_string(),
+                "client_credentials".to_string(),
+            ))
+            .with_status(200)
+            .with_header(CONTENT_TYPE.as_str(), "application/json")
+            .with_body(
+                serde_json::json!({
+                    "access_token": token,
+                    "token_type": "bearer",
+                    "expires_in": 2
+                })
+                .to_string(),
+            )
+            .expect(expect)
+            .create()
+    }
+
+    #[test]
+    fn test_blocking_refresh() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/token")
+            .with_status(200)
+            .with_header(CONTENT_TYPE.as_str(), "application/json")
+            .with_body(
+                serde_json::json!({
+                    "access_token": "tok",
+                    "token_type": "bearer",
+                    "expires_in": 60
+                })
+                .to_string(),
+            )
+            .expect_at_least(2)
+            .create();
+
+        let authorizer = BlockingClientCredentialAuthorizer::builder(
+            "my-client",
+            "my-secret",
+            format!("{}/token", server.url()).parse().unwrap(),
+        )
+        .refresh_tolerance(Duration::from_secs(59))
+        .build()
+        .unwrap();
+        assert_eq!(
+            authorizer.authorization_header().unwrap().to_str().unwrap(),
+            "Bearer tok"
+        );
+        assert!(authorizer.health().is_healthy());
+
+        // The token is refreshed 59 seconds before expiry, i.e. after 1 second.
+        let initial = authorizer.health().last_refresh;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while authorizer.health().last_refresh == initial {
+            assert!(Instant::now() < deadline, "token was not refreshed");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        mock.assert();
+
+        // The refresh thread stops and releases its handle.
+        let inner = Arc::downgrade(&authorizer.inner);
+        assert!(!authorizer.refresh_thread().unwrap().is_finished());
+        drop(authorizer);
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(inner.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_blocking_refresh_now() {
+        let mut server = mockito::Server::new();
+        let mock = token_mock(&mut server, "tok", 2);
+
+        let authorizer = BlockingClientCredentialAuthorizer::builder(
+            "my-client",
+            "my-secret",
+            format!("{}/token", server.url()).parse().unwrap(),
+        )
+        .disable_refresh()
+        .build()
+        .unwrap();
+        assert!(authorizer.refresh_thread().is_none());
+
+        authorizer.refresh_now().unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_blocking_initial_fetch_fails() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/token")
+            .with_status(500)
+            .expect(2)
+            .create();
+
+        let result = BlockingClientCredentialAuthorizer::builder(
+            "my-client",
+            "my-secret",
+            format!("{}/token", server.url()).parse().unwrap(),
+        )
+        .set_max_retries(1)
+        .build();
+        assert!(matches!(result, Err(Error::OAuth2RequestFailed(_))));
+        mock.assert();
+    }
+}
@@ -0,0 +1,818 @@
+//! Authorizers combining multiple inner authorizers.
+use std::{
+    fmt::Display,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use http::HeaderValue;
+
+use super::{Authorizer, AuthorizerHealth, CredentialLocation, HealthStatus, RefreshFuture};
+use crate::error::{Error, Result};
+
+/// How long [`FirstHealthy`] reuses the order of its authorizers before checking
+/// their health again.
+const FIRST_HEALTHY_ORDER_TTL: Duration = Duration::from_secs(1);
+
+/// Which authorizer of a [`FallbackAuthorizer`] provided a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackSource {
+    /// The primary authorizer.
+    Primary,
+    /// The secondary authorizer, used because the primary failed.
+    Secondary,
+}
+
+/// Uses a primary authorizer and falls back to a secondary one, for example a
+/// static break-glass token, if the primary fails to provide a header.
+///
+/// [`Authorizer::health`] reports [`HealthStatus::Degraded`] while only the
+/// secondary authorizer works. A warning is logged when the primary authorizer
+/// starts failing, not for every request.
+///
+/// Both authorizers must place their credentials at the same
+/// [`CredentialLocation`], so that a fallback credential is never sent where the
+/// primary one belongs, for example in a query parameter.
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`tonic::service::Interceptor`] is implemented for
+/// [`FallbackAuthorizer`]. The interceptor does not insert the access token if the intercepted call
+/// already has an `Authorization` header.
+#[derive(Debug)]
+pub struct FallbackAuthorizer<P, S> {
+    primary: P,
+    secondary: S,
+    primary_failing: AtomicBool,
+}
+
+impl<P: Clone, S: Clone> Clone for FallbackAuthorizer<P, S> {
+    fn clone(&self) -> Self {
+        Self {
+            primary: self.primary.clone(),
+            secondary: self.secondary.clone(),
+            primary_failing: AtomicBool::new(self.primary_failing.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<P: Authorizer, S: Authorizer> FallbackAuthorizer<P, S> {
+    /// Create a new authorizer that falls back to `secondary` if `primary` fails.
+    ///
+    /// # Errors
+    /// Fails with [`Error::InvalidConfig`] if the authorizers place their credentials
+    /// at different [`CredentialLocation`]s.
+    pub fn new(primary: P, secondary: S) -> Result<Self> {
+        check_locations([
+            primary.credential_location(),
+            secondary.credential_location(),
+        ])?;
+        Ok(Self {
+            primary,
+            secondary,
+            primary_failing: AtomicBool::new(false),
+        })
+    }
+
+    /// The primary authorizer.
+    #[must_use]
+    pub fn primary(&self) -> &P {
+        &self.primary
+    }
+
+    /// The secondary authorizer.
+    #[must_use]
+    pub fn secondary(&self) -> &S {
+        &self.secondary
+    }
+
+    /// Returns the authorization header together with the authorizer that provided it.
+    ///
+    /// # Errors
+    /// Fails with the error of the secondary authorizer if both authorizers fail.
+    pub fn authorization_header_with_source(&self) -> Result<(Arc<HeaderValue>, FallbackSource)> {
+        match self.primary.authorization_header() {
+            Ok(header) => {
+                self.primary_succeeded();
+                Ok((header, FallbackSource::Primary))
+            }
+            Err(e) => {
+                self.primary_failed(&e);
+                self.secondary
+                    .authorization_header()
+                    .map(|header| (header, FallbackSource::Secondary))
+            }
+        }
+    }
+
+    /// Like [`Self::authorization_header_with_source`], but returns `None` if the
+    /// chosen authorizer sends requests without credentials.
+    fn optional_authorization_header_with_source(
+        &self,
+    ) -> Result<(Option<Arc<HeaderValue>>, FallbackSource)> {
+        match self.primary.optional_authorization_header() {
+            Ok(header) => {
+                self.primary_succeeded();
+                Ok((header, FallbackSource::Primary))
+            }
+            Err(e) => {
+                self.primary_failed(&e);
+                self.secondary
+                    .optional_authorization_header()
+                    .map(|header| (header, FallbackSource::Secondary))
+            }
+        }
+    }
+
+    /// Log if the primary authorizer recovered.
+    fn primary_succeeded(&self) {
+        if self.primary_failing.swap(false, Ordering::Relaxed) {
+            tracing::info!("Primary authorizer recovered.");
+        }
+    }
+
+    /// Log if the primary authorizer started failing.
+    fn primary_failed(&self, error: &dyn Display) {
+        if self.primary_failing.swap(true, Ordering::Relaxed) {
+            tracing::debug!("Primary authorizer failed: {error}. Using secondary authorizer.");
+        } else {
+            tracing::warn!("Primary authorizer failed: {error}. Using secondary authorizer.");
+        }
+    }
+}
+
+impl<P: Authorizer, S: Authorizer> Authorizer for FallbackAuthorizer<P, S> {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        self.authorization_header_with_source()
+            .map(|(header, _)| header)
+    }
+
+    fn optional_authorization_header(&self) -> Result<Option<Arc<HeaderValue>>> {
+        self.optional_authorization_header_with_source()
+            .map(|(header, _)| header)
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        self.primary.credential_location()
+    }
+
+    fn health(&self) -> AuthorizerHealth {
+        let primary = self.primary.health();
+        if primary.is_healthy() {
+            return primary;
+        }
+        match status_error(&primary.status) {
+            Some(error) if self.secondary.health().is_ready() => AuthorizerHealth {
+                status: HealthStatus::Degraded {
+                    last_error: error.clone(),
+                },
+                ..primary
+            },
+            _ => primary,
+        }
+    }
+
+    /// Refreshes both authorizers. Fails only if both fail.
+    fn refresh(&self) -> RefreshFuture<'_> {
+        Box::pin(refresh_all(vec![
+            self.primary.refresh(),
+            self.secondary.refresh(),
+        ]))
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        match self.primary.authorization_header_tonic() {
+            Ok(metadata) => {
+                self.primary_succeeded();
+                Ok(metadata)
+            }
+            Err(status) => {
+                self.primary_failed(&status.message());
+                self.secondary.authorization_header_tonic()
+            }
+        }
+    }
+}
+
+/// Distributes requests across multiple authorizers in turn, for example clients
+/// of multiple Identity Provider endpoints.
+///
+/// If an authorizer fails, the next one is tried. The call fails only if all
+/// authorizers fail.
+///
+/// All authorizers must place their credentials at the same
+/// [`CredentialLocation`].
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`tonic::service::Interceptor`] is implemented for
+/// [`RoundRobinAuthorizer`]. The interceptor does not insert the access token if the intercepted call
+/// already has an `Authorization` header.
+#[derive(Debug)]
+pub struct RoundRobinAuthorizer<A> {
+    authorizers: Vec<A>,
+    next: AtomicUsize,
+}
+
+impl<A: Clone> Clone for RoundRobinAuthorizer<A> {
+    fn clone(&self) -> Self {
+        Self {
+            authorizers: self.authorizers.clone(),
+            next: AtomicUsize::new(self.next.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<A: Authorizer> RoundRobinAuthorizer<A> {
+    /// Create a new authorizer rotating through `authorizers`.
+    ///
+    /// # Errors
+    /// Fails with [`Error::InvalidConfig`] if `authorizers` is empty or the authorizers
+    /// place their credentials at different [`CredentialLocation`]s.
+    pub fn new(authorizers: Vec<A>) -> Result<Self> {
+        check_locations(authorizers.iter().map(Authorizer::credential_location))?;
+        Ok(Self {
+            authorizers,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// The inner authorizers.
+    #[must_use]
+    pub fn authorizers(&self) -> &[A] {
+        &self.authorizers
+    }
+
+    /// Returns the authorization header together with the index of the authorizer
+    /// in [`Self::authorizers`] that provided it.
+    ///
+    /// # Errors
+    /// Fails with the error of the last authorizer tried if all authorizers fail.
+    pub fn authorization_header_with_source(&self) -> Result<(Arc<HeaderValue>, usize)> {
+        first_ok(self.order(), |i| self.authorizers[i].authorization_header())
+    }
+
+    /// Indices of all authorizers, starting with the next one in turn.
+    fn order(&self) -> impl Iterator<Item = usize> {
+        let len = self.authorizers.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+        (0..len).map(move |offset| (start + offset) % len)
+    }
+}
+
+impl<A: Authorizer> Authorizer for RoundRobinAuthorizer<A> {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        self.authorization_header_with_source()
+            .map(|(header, _)| header)
+    }
+
+    fn optional_authorization_header(&self) -> Result<Option<Arc<HeaderValue>>> {
+        first_ok(self.order(), |i| {
+            self.authorizers[i].optional_authorization_header()
+        })
+        .map(|(header, _)| header)
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        self.authorizers[0].credential_location()
+    }
+
+    /// Healthy if all authorizers are healthy, degraded if at least one can
+    /// authorize requests.
+    fn health(&self) -> AuthorizerHealth {
+        let healths: Vec<_> = self.authorizers.iter().map(Authorizer::health).collect();
+        let Some(unhealthy) = healths.iter().find(|health| !health.is_healthy()) else {
+            return AuthorizerHealth::new(HealthStatus::Healthy);
+        };
+        combined_health(&healths, unhealthy)
+    }
+
+    /// Refreshes all authorizers. Fails only if all fail.
+    fn refresh(&self) -> RefreshFuture<'_> {
+        Box::pin(refresh_all(
+            self.authorizers.iter().map(Authorizer::refresh).collect(),
+        ))
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        first_ok(self.order(), |i| {
+            self.authorizers[i].authorization_header_tonic()
+        })
+        .map(|(metadata, _)| metadata)
+    }
+}
+
+/// Uses the first healthy of multiple authorizers, for example clients of a
+/// primary and a standby Identity Provider.
+///
+/// Authorizers are preferred in order: the first [healthy](AuthorizerHealth::is_healthy)
+/// one, then the first [ready](AuthorizerHealth::is_ready) one. If the chosen authorizer
+/// fails to provide a header, the remaining ones are tried. The health of the
+/// authorizers is checked at most once per second.
+///
+/// All authorizers must place their credentials at the same
+/// [`CredentialLocation`].
+///
+/// ## Tonic
+/// If the `tonic` feature is enabled, [`tonic::service::Interceptor`] is implemented for
+/// [`FirstHealthy`]. The interceptor does not insert the access token if the intercepted call
+/// already has an `Authorization` header.
+#[derive(Debug)]
+pub struct FirstHealthy<A> {
+    authorizers: Vec<A>,
+    /// Last result of [`Self::order`] and when it was computed.
+    order: Mutex<Option<(Instant, Arc<[usize]>)>>,
+}
+
+impl<A: Clone> Clone for FirstHealthy<A> {
+    fn clone(&self) -> Self {
+        Self {
+            authorizers: self.authorizers.clone(),
+            order: Mutex::new(self.order.lock().expect("Non-poisoned lock").clone()),
+        }
+    }
+}
+
+impl<A: Authorizer> FirstHealthy<A> {
+    /// Create a new authorizer choosing from `authorizers` in order.
+    ///
+    /// # Errors
+    /// Fails with [`Error::InvalidConfig`] if `authorizers` is empty or the authorizers
+    /// place their credentials at different [`CredentialLocation`]s.
+    pub fn new(authorizers: Vec<A>) -> Result<Self> {
+        check_locations(authorizers.iter().map(Authorizer::credential_location))?;
+        Ok(Self {
+            authorizers,
+            order: Mutex::new(None),
+        })
+    }
+
+    /// The inner authorizers.
+    #[must_use]
+    pub fn authorizers(&self) -> &[A] {
+        &self.authorizers
+    }
+
+    /// Returns the authorization header together with the index of the authorizer
+    /// in [`Self::authorizers`] that provided it.
+    ///
+    /// # Errors
+    /// Fails with the error of the last authorizer tried if all authorizers fail.
+    pub fn authorization_header_with_source(&self) -> Result<(Arc<HeaderValue>, usize)> {
+        first_ok(self.order(), |i| self.authorizers[i].authorization_header())
+    }
+
+    /// Indices of all authorizers: healthy ones first, then ready ones, then the rest.
+    /// Reused for [`FIRST_HEALTHY_ORDER_TTL`].
+    fn order(&self) -> impl Iterator<Item = usize> {
+        let now = Instant::now();
+        let mut cached = self.order.lock().expect("Non-poisoned lock");
+        let order = match &*cached {
+            Some((computed_at, order))
+                if now.duration_since(*computed_at) < FIRST_HEALTHY_ORDER_TTL =>
+            {
+                order.clone()
+            }
+            _ => {
+                let order = self.rank();
+                *cached = Some((now, order.clone()));
+                order
+            }
+        };
+        drop(cached);
+        (0..order.len()).map(move |i| order[i])
+    }
+
+    /// Rank the authorizers by their current health.
+    fn rank(&self) -> Arc<[usize]> {
+        let mut order: Vec<(u8, usize)> = self
+            .authorizers
+            .iter()
+            .enumerate()
+            .map(|(i, authorizer)| {
+                let health = authorizer.health();
+                let rank = if health.is_healthy() {
+                    0
+                } else if health.is_ready() {
+                    1
+                } else {
+                    2
+                };
+                (rank, i)
+            })
+            .collect();
+        order.sort_unstable();
+        order.into_iter().map(|(_, i)| i).collect()
+    }
+}
+
+impl<A: Authorizer> Authorizer for FirstHealthy<A> {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        self.authorization_header_with_source()
+            .map(|(header, _)| header)
+    }
+
+    fn optional_authorization_header(&self) -> Result<Option<Arc<HeaderValue>>> {
+        first_ok(self.order(), |i| {
+            self.authorizers[i].optional_authorization_header()
+        })
+        .map(|(header, _)| header)
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        self.authorizers[0].credential_location()
+    }
+
+    /// Healthy if any authorizer is healthy, degraded if at least one can
+    /// authorize requests.
+    fn health(&self) -> AuthorizerHealth {
+        let healths: Vec<_> = self.authorizers.iter().map(Authorizer::health).collect();
+        if let Some(healthy) = healths.iter().find(|health| health.is_healthy()) {
+            return healthy.clone();
+        }
+        combined_health(&healths, &healths[0])
+    }
+
+    /// Refreshes all authorizers. Fails only if all fail.
+    fn refresh(&self) -> RefreshFuture<'_> {
+        Box::pin(refresh_all(
+            self.authorizers.iter().map(Authorizer::refresh).collect(),
+        ))
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> std::result::Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status>
+    {
+        first_ok(self.order(), |i| {
+            self.authorizers[i].authorization_header_tonic()
+        })
+        .map(|(metadata, _)| metadata)
+    }
+}
+
+/// Ensure that all authorizers of a composite place their credentials at the same
+/// location, and that there is at least one.
+fn check_locations(locations: impl IntoIterator<Item = CredentialLocation>) -> Result<()> {
+    let mut locations = locations.into_iter();
+    let Some(first) = locations.next() else {
+        return Err(Error::InvalidConfig(
+            "At least one authorizer is required".to_string(),
+        ));
+    };
+    match locations.find(|location| *location != first) {
+        Some(other) => Err(Error::InvalidConfig(format!(
+            "All authorizers must use the same credential location, got {first:?} and {other:?}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Call `f` for the indices in `order` until it succeeds.
+/// Returns the result and the index, or the last error.
+fn first_ok<T, E: std::fmt::Display>(
+    order: impl Iterator<Item = usize>,
+    f: impl Fn(usize) -> std::result::Result<T, E>,
+) -> std::result::Result<(T, usize), E> {
+    let mut last_error = None;
+    for i in order {
+        match f(i) {
+            Ok(value) => return Ok((value, i)),
+            Err(e) => {
+                tracing::debug!("Authorizer {i} failed: {e}. Trying next authorizer.");
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.expect("At least one authorizer"))
+}
+
+/// Await `refreshes` in order. Succeeds if any refresh succeeds, otherwise fails
+/// with the last error.
+async fn refresh_all(refreshes: Vec<RefreshFuture<'_>>) -> Result<()> {
+    let mut any_ok = false;
+    let mut last_error = None;
+    for refresh in refreshes {
+        match refresh.await {
+            Ok(()) => any_ok = true,
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) if !any_ok => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn status_error(status: &HealthStatus) -> Option<&Error> {
+    match status {
+        HealthStatus::Healthy => None,
+        HealthStatus::Degraded { last_error } => Some(last_error),
+        HealthStatus::Unhealthy { error } => Some(error),
+    }
+}
+
+/// Health of a group of authorizers that are not all healthy: degraded if any
+/// authorizer is ready, unhealthy otherwise. Reports the error of `unhealthy`.
+fn combined_health(healths: &[AuthorizerHealth], unhealthy: &AuthorizerHealth) -> AuthorizerHealth {
+    let error = status_error(&unhealthy.status)
+        .cloned()
+        .unwrap_or(Error::TokenExpired);
+    let status = if healths.iter().any(AuthorizerHealth::is_ready) {
+        HealthStatus::Degraded { last_error: error }
+    } else {
+        HealthStatus::Unhealthy { error }
+    };
+    AuthorizerHealth {
+        status,
+        ..unhealthy.clone()
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl<P: Authorizer, S: Authorizer> tonic::service::Interceptor for FallbackAuthorizer<P, S> {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        super::intercept(self, request)
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl<A: Authorizer> tonic::service::Interceptor for RoundRobinAuthorizer<A> {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        super::intercept(self, request)
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl<A: Authorizer> tonic::service::Interceptor for FirstHealthy<A> {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        super::intercept(self, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorizers::BearerTokenAuthorizer;
+
+    /// Authorizer that never provides a header.
+    struct Failing;
+
+    impl Authorizer for Failing {
+        fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+            Err(Error::TokenExpired)
+        }
+    }
+
+    fn bearer(token: &str) -> Box<dyn Authorizer> {
+        Box::new(BearerTokenAuthorizer::new(token).unwrap())
+    }
+
+    fn header_str(header: &HeaderValue) -> &str {
+        header.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_fallback() {
+        let authorizer = FallbackAuthorizer::new(bearer("primary"), bearer("secondary")).unwrap();
+        let (header, source) = authorizer.authorization_header_with_source().unwrap();
+        assert_eq!(header_str(&header), "Bearer primary");
+        assert_eq!(source, FallbackSource::Primary);
+        assert!(authorizer.health().is_healthy());
+
+        let authorizer = FallbackAuthorizer::new(Failing, bearer("secondary")).unwrap();
+        let (header, source) = authorizer.authorization_header_with_source().unwrap();
+        assert_eq!(header_str(&header), "Bearer secondary");
+        assert_eq!(source, FallbackSource::Secondary);
+        assert!(matches!(
+            authorizer.health().status,
+            HealthStatus::Degraded {
+                last_error: Error::TokenExpired
+            }
+        ));
+
+        let authorizer = FallbackAuthorizer::new(Failing, Failing).unwrap();
+        assert!(authorizer.authorization_header().is_err());
+        assert!(!authorizer.health().is_ready());
+    }
+
+    #[test]
+    fn test_mismatched_locations() {
+        use crate::authorizers::ApiKeyAuthorizer;
+
+        let api_key = || ApiKeyAuthorizer::query("api_key", "my-key").unwrap();
+        assert!(matches!(
+            FallbackAuthorizer::new(api_key(), BearerTokenAuthorizer::new("secondary").unwrap()),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            RoundRobinAuthorizer::new(vec![bearer("a"), Box::new(api_key())]),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            FirstHealthy::new(vec![bearer("a"), Box::new(api_key())]),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            FirstHealthy::<Box<dyn Authorizer>>::new(vec![]),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_optional_header() {
+        use crate::authorizers::AnyAuthorizer;
+
+        let authorizer = FallbackAuthorizer::new(Failing, AnyAuthorizer::None).unwrap();
+        assert!(
+            authorizer
+                .optional_authorization_header()
+                .unwrap()
+                .is_none()
+        );
+
+        let authorizer = RoundRobinAuthorizer::new(vec![
+            Box::new(Failing) as Box<dyn Authorizer>,
+            Box::new(AnyAuthorizer::None),
+        ])
+        .unwrap();
+        assert!(
+            authorizer
+                .optional_authorization_header()
+                .unwrap()
+                .is_none()
+        );
+
+        let authorizer =
+            FirstHealthy::new(vec![Box::new(AnyAuthorizer::None), bearer("b")]).unwrap();
+        assert!(
+            authorizer
+                .optional_authorization_header()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_fallback_warns_once() {
+        let authorizer = FallbackAuthorizer::new(Failing, bearer("secondary")).unwrap();
+        for _ in 0..3 {
+            authorizer.authorization_header().unwrap();
+        }
+        logs_assert(|lines: &[&str]| {
+            match lines
+                .iter()
+                .filter(|line| line.contains("WARN") && line.contains("Primary authorizer failed"))
+                .count()
+            {
+                1 => Ok(()),
+                n => Err(format!("Expected one warning, got {n}")),
+            }
+        });
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let authorizer =
+            RoundRobinAuthorizer::new(vec![bearer("a"), Box::new(Failing), bearer("c")]).unwrap();
+        let sources: Vec<_> = (0..4)
+            .map(|_| authorizer.authorization_header_with_source().unwrap().1)
+            .collect();
+        // The failing authorizer is skipped.
+        assert_eq!(sources, vec![0, 2, 2, 0]);
+        assert!(matches!(
+            authorizer.health().status,
+            HealthStatus::Degraded { .. }
+        ));
+    }
+
+    #[test]
+    fn test_first_healthy() {
+        let authorizer =
+            FirstHealthy::new(vec![Box::new(Failing), bearer("b"), bearer("c")]).unwrap();
+        let (header, source) = authorizer.authorization_header_with_source().unwrap();
+        assert_eq!(header_str(&header), "Bearer b");
+        assert_eq!(source, 1);
+        assert!(authorizer.health().is_healthy());
+
+        let authorizer = FirstHealthy::new(vec![Box::new(Failing) as Box<dyn Authorizer>]).unwrap();
+        assert!(authorizer.authorization_header().is_err());
+        assert!(!authorizer.health().is_ready());
+    }
+
+    /// Authorizer counting its refreshes, failing them if `fail` is set.
+    #[derive(Default)]
+    struct Refreshing {
+        refreshes: AtomicUsize,
+        fail: bool,
+    }
+
+    impl Authorizer for Refreshing {
+        fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+            Err(Error::TokenExpired)
+        }
+
+        fn refresh(&self) -> RefreshFuture<'_> {
+            self.refreshes.fetch_add(1, Ordering::SeqCst);
+            let result = if self.fail {
+                Err(Error::TokenExpired)
+            } else {
+                Ok(())
+            };
+            Box::pin(std::future::ready(result))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_is_forwarded() {
+        let failing = || Refreshing {
+            fail: true,
+            ..Default::default()
+        };
+
+        let authorizer = FallbackAuthorizer::new(failing(), Refreshing::default()).unwrap();
+        authorizer.refresh().await.unwrap();
+        assert_eq!(authorizer.primary().refreshes.load(Ordering::SeqCst), 1);
+        assert_eq!(authorizer.secondary().refreshes.load(Ordering::SeqCst), 1);
+
+        let authorizer = RoundRobinAuthorizer::new(vec![failing(), failing()]).unwrap();
+        assert!(authorizer.refresh().await.is_err());
+        assert!(
+            authorizer
+                .authorizers()
+                .iter()
+                .all(|a| a.refreshes.load(Ordering::SeqCst) == 1)
+        );
+
+        let authorizer = FirstHealthy::new(vec![failing(), Refreshing::default()]).unwrap();
+        authorizer.refresh().await.unwrap();
+        assert!(
+            authorizer
+                .authorizers()
+                .iter()
+                .all(|a| a.refreshes.load(Ordering::SeqCst) == 1)
+        );
+    }
+
+    /// Healthy authorizer counting its health checks.
+    #[derive(Default)]
+    struct CountingHealth(AtomicUsize);
+
+    impl Authorizer for CountingHealth {
+        fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+            Ok(Arc::new(HeaderValue::from_static("Bearer counted")))
+        }
+
+        fn health(&self) -> AuthorizerHealth {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            AuthorizerHealth::new(HealthStatus::Healthy)
+        }
+    }
+
+    #[test]
+    fn test_first_healthy_caches_order() {
+        let authorizer = FirstHealthy::new(vec![CountingHealth::default()]).unwrap();
+        for _ in 0..3 {
+            authorizer.authorization_header().unwrap();
+        }
+        assert_eq!(authorizer.authorizers()[0].0.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn test_interceptor() {
+        use tonic::service::Interceptor;
+
+        let mut authorizer = FallbackAuthorizer::new(Failing, bearer("secondary")).unwrap();
+        let request = authorizer.call(tonic::Request::new(())).unwrap();
+        assert_eq!(
+            request
+                .metadata()
+                .get("authorization")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Bearer secondary"
+        );
+    }
+}
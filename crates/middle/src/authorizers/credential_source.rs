@@ -0,0 +1,216 @@
+//! Sources for client secrets that may change during the lifetime of an authorizer.
+use std::{
+    fmt::Debug,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use oauth2::ClientSecret;
+
+use crate::error::Error;
+
+/// Future returned by [`CredentialSource::client_secret`].
+pub type CredentialFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<ClientSecret, Error>> + Send + 'a>>;
+
+/// Provides the client secret used by a
+/// [`ClientCredentialAuthorizer`](super::ClientCredentialAuthorizer).
+///
+/// The source is consulted before every token request, so rotated secrets are
+/// picked up without rebuilding the authorizer. Register a source with
+/// [`ClientCredentialAuthorizerBuilder::credential_source`](super::ClientCredentialAuthorizerBuilder::credential_source).
+///
+/// Implementations are provided for static secrets ([`StaticCredential`]),
+/// environment variables ([`EnvCredential`]), files ([`FileCredential`]) and
+/// custom async functions ([`FnCredential`]).
+pub trait CredentialSource: Debug + Send + Sync {
+    /// Returns the current client secret.
+    ///
+    /// # Errors
+    /// Fails if the secret is not available. The token request fails with this error.
+    fn client_secret(&self) -> CredentialFuture<'_>;
+}
+
+/// A client secret that never changes.
+#[derive(Debug, Clone)]
+pub struct StaticCredential(ClientSecret);
+
+impl StaticCredential {
+    /// Create a source that always returns `secret`.
+    #[must_use]
+    pub fn new(secret: &str) -> Self {
+        Self(ClientSecret::new(secret.to_string()))
+    }
+}
+
+impl CredentialSource for StaticCredential {
+    fn client_secret(&self) -> CredentialFuture<'_> {
+        Box::pin(std::future::ready(Ok(self.0.clone())))
+    }
+}
+
+/// Reads the client secret from an environment variable on every token request.
+#[derive(Debug, Clone)]
+pub struct EnvCredential {
+    variable: String,
+}
+
+impl EnvCredential {
+    /// Create a source that reads the environment variable `variable`.
+    #[must_use]
+    pub fn new(variable: &str) -> Self {
+        Self {
+            variable: variable.to_string(),
+        }
+    }
+}
+
+impl CredentialSource for EnvCredential {
+    fn client_secret(&self) -> CredentialFuture<'_> {
+        let secret = std::env::var(&self.variable)
+            .map(ClientSecret::new)
+            .map_err(|e| {
+                Error::CredentialSource(format!(
+                    "Failed to read environment variable `{}`: {e}",
+                    self.variable
+                ))
+            });
+        Box::pin(std::future::ready(secret))
+    }
+}
+
+/// Reads the client secret from a file, for example a mounted Kubernetes secret.
+///
+/// The file is read again whenever its modification time changes, so rotated
+/// secrets are picked up with the next token request. Leading and trailing
+/// whitespace (such as a final newline) is removed.
+#[derive(Debug)]
+pub struct FileCredential {
+    path: PathBuf,
+    cached: Mutex<Option<(SystemTime, ClientSecret)>>,
+}
+
+impl FileCredential {
+    /// Create a source that reads the file at `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The path of the secret file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<ClientSecret, Error> {
+        let io_error = |e: std::io::Error| {
+            Error::CredentialSource(format!("Failed to read `{}`: {e}", self.path.display()))
+        };
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(io_error)?;
+
+        let mut cached = self.cached.lock().expect("Non-poisoned lock");
+        if let Some((cached_modified, secret)) = &*cached
+            && *cached_modified == modified
+        {
+            return Ok(secret.clone());
+        }
+
+        let secret = std::fs::read_to_string(&self.path).map_err(io_error)?;
+        let secret = ClientSecret::new(secret.trim().to_string());
+        tracing::debug!("Loaded client secret from `{}`.", self.path.display());
+        *cached = Some((modified, secret.clone()));
+        Ok(secret)
+    }
+}
+
+impl CredentialSource for FileCredential {
+    fn client_secret(&self) -> CredentialFuture<'_> {
+        Box::pin(std::future::ready(self.read()))
+    }
+}
+
+/// Obtains the client secret from a custom async function, for example a call to
+/// a secret manager.
+pub struct FnCredential<F> {
+    provider: F,
+}
+
+impl<F, Fut> FnCredential<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<ClientSecret, Error>> + Send + 'static,
+{
+    /// Create a source that calls `provider` before every token request.
+    #[must_use]
+    pub fn new(provider: F) -> Self {
+        Self { provider }
+    }
+}
+
+impl<F> Debug for FnCredential<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FnCredential")
+    }
+}
+
+impl<F, Fut> CredentialSource for FnCredential<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<ClientSecret, Error>> + Send + 'static,
+{
+    fn client_secret(&self) -> CredentialFuture<'_> {
+        Box::pin((self.provider)())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_credential_reloads_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret");
+        std::fs::write(&path, "first-secret\n").unwrap();
+
+        let source = FileCredential::new(&path);
+        assert_eq!(
+            source.client_secret().await.unwrap().secret(),
+            "first-secret"
+        );
+
+        std::fs::write(&path, "second-secret").unwrap();
+        // Make sure the modification time changes even on coarse-grained filesystems.
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(
+            source.client_secret().await.unwrap().secret(),
+            "second-secret"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            source.client_secret().await,
+            Err(Error::CredentialSource(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fn_credential() {
+        let source = FnCredential::new(|| async { Ok(ClientSecret::new("dynamic".to_string())) });
+        assert_eq!(source.client_secret().await.unwrap().secret(), "dynamic");
+    }
+}
@@ -0,0 +1,72 @@
+//! Events emitted by authorizers that refresh their credentials in the background.
+use std::{fmt, sync::Arc, time::Duration};
+
+use tokio::sync::broadcast;
+
+use crate::error::Error;
+
+/// Number of events buffered per subscriber before the oldest ones are dropped.
+const EVENT_CHANNEL_CAPACITY: usize = 16;
+
+/// Lifecycle event of a refreshing authorizer.
+///
+/// Obtain a receiver with [`ClientCredentialAuthorizer::subscribe`](crate::ClientCredentialAuthorizer::subscribe)
+/// or register a callback with
+/// [`ClientCredentialAuthorizerBuilder::on_event`](crate::ClientCredentialAuthorizerBuilder::on_event).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum AuthorizerEvent {
+    /// A new token was fetched from the Identity Provider.
+    /// `expires_in` is `None` if the token does not expire.
+    TokenRefreshed { expires_in: Option<Duration> },
+    /// Fetching a new token failed after all retries.
+    /// `attempt` is the number of consecutive failed refreshes, starting at 1.
+    RefreshFailed { error: Error, attempt: u32 },
+    /// The cached token expired while refreshes kept failing. Requests fail
+    /// until the next successful refresh.
+    TokenExpired,
+    /// A refresh succeeded after one or more failed refreshes.
+    Recovered,
+}
+
+/// Callback invoked for every [`AuthorizerEvent`].
+#[derive(Clone)]
+pub(crate) struct EventCallback(Arc<dyn Fn(&AuthorizerEvent) + Send + Sync>);
+
+impl EventCallback {
+    pub(crate) fn new(callback: impl Fn(&AuthorizerEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for EventCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventCallback")
+    }
+}
+
+/// Fans events out to the registered callbacks and all channel subscribers.
+#[derive(Debug)]
+pub(crate) struct EventEmitter {
+    sender: broadcast::Sender<AuthorizerEvent>,
+    callbacks: Vec<EventCallback>,
+}
+
+impl EventEmitter {
+    pub(crate) fn new(callbacks: Vec<EventCallback>) -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender, callbacks }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<AuthorizerEvent> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn emit(&self, event: AuthorizerEvent) {
+        for callback in &self.callbacks {
+            (callback.0)(&event);
+        }
+        // Sending only fails if nobody is subscribed, which is fine.
+        self.sender.send(event).ok();
+    }
+}
@@ -0,0 +1,281 @@
+//! Per-RPC selection of the authorizer used for `tonic` calls.
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    sync::Arc,
+};
+
+use super::Authorizer;
+use crate::error::Error;
+
+/// Type-erased authorizer shared by a [`GrpcAuthRouter`] and [`CallCredentials`].
+type DynAuthorizer = Arc<dyn Authorizer + Send + Sync>;
+
+/// Credentials for a single gRPC call, attached as a request extension and
+/// evaluated by a [`GrpcAuthRouter`].
+///
+/// ```
+/// use middle::{BearerTokenAuthorizer, CallCredentials};
+///
+/// let mut request = tonic::Request::new(());
+/// CallCredentials::scopes(["admin"]).apply(&mut request);
+///
+/// let mut request = tonic::Request::new(());
+/// CallCredentials::authorizer(BearerTokenAuthorizer::new("token").unwrap()).apply(&mut request);
+/// ```
+#[derive(Clone)]
+pub enum CallCredentials {
+    /// Use this authorizer instead of the one selected by the router.
+    Authorizer(DynAuthorizer),
+    /// Use the authorizer registered for this set of scopes with
+    /// [`GrpcAuthRouter::scopes`].
+    Scopes(BTreeSet<String>),
+}
+
+impl CallCredentials {
+    /// Override the authorizer of the call.
+    #[must_use]
+    pub fn authorizer(authorizer: impl Authorizer + Send + Sync + 'static) -> Self {
+        Self::Authorizer(Arc::new(authorizer))
+    }
+
+    /// Use the authorizer registered for `scopes`. The order of the scopes does not matter.
+    #[must_use]
+    pub fn scopes<I, S>(scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Scopes(scopes.into_iter().map(Into::into).collect())
+    }
+
+    /// Attach these credentials to `request`, replacing previously attached ones.
+    pub fn apply<T>(self, request: &mut tonic::Request<T>) {
+        request.extensions_mut().insert(self);
+    }
+}
+
+impl Debug for CallCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Authorizer(_) => f.debug_tuple("Authorizer").finish_non_exhaustive(),
+            Self::Scopes(scopes) => f.debug_tuple("Scopes").field(scopes).finish(),
+        }
+    }
+}
+
+/// [`tonic::service::Interceptor`] selecting the authorizer for each call, so that
+/// different methods on the same channel can use different tokens or scopes.
+///
+/// The authorizer is selected in the following order:
+/// 1. [`CallCredentials`] attached to the request: either the given authorizer, or the
+///    authorizer registered for the given scopes with [`GrpcAuthRouter::scopes`].
+/// 2. The authorizer routed for the method path, as registered with
+///    [`GrpcAuthRouter::route`]. The path is read from the [`tonic::GrpcMethod`]
+///    extension that generated clients add to each request.
+/// 3. The default authorizer, see [`GrpcAuthRouter::with_default`].
+///
+/// Calls fail with an `unauthenticated` status if no authorizer matches. As with the
+/// interceptors of the authorizers, the credentials are not inserted if the call already
+/// has the header.
+///
+/// ```
+/// use middle::{BearerTokenAuthorizer, GrpcAuthRouter};
+///
+/// let router = GrpcAuthRouter::with_default(BearerTokenAuthorizer::new("default").unwrap())
+///     .route("/admin.v1.AdminService/*", BearerTokenAuthorizer::new("admin").unwrap())
+///     .route(
+///         "/catalog.v1.CatalogService/DropTable",
+///         BearerTokenAuthorizer::new("admin").unwrap(),
+///     )
+///     .scopes(["catalog:write"], BearerTokenAuthorizer::new("writer").unwrap());
+/// ```
+#[derive(Clone, Default)]
+pub struct GrpcAuthRouter {
+    default: Option<DynAuthorizer>,
+    routes: HashMap<String, DynAuthorizer>,
+    scopes: HashMap<BTreeSet<String>, DynAuthorizer>,
+}
+
+impl GrpcAuthRouter {
+    /// Create a router without a default authorizer: calls without a matching route
+    /// or [`CallCredentials`] fail.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a router using `authorizer` for calls without a matching route or
+    /// [`CallCredentials`].
+    #[must_use]
+    pub fn with_default(authorizer: impl Authorizer + Send + Sync + 'static) -> Self {
+        Self {
+            default: Some(Arc::new(authorizer)),
+            ..Self::default()
+        }
+    }
+
+    /// Use `authorizer` for calls to `path`, either a method as
+    /// `/package.Service/Method`, or all methods of a service as `/package.Service/*`.
+    /// Method routes take precedence over service routes.
+    #[must_use]
+    pub fn route(
+        mut self,
+        path: impl Into<String>,
+        authorizer: impl Authorizer + Send + Sync + 'static,
+    ) -> Self {
+        self.routes.insert(path.into(), Arc::new(authorizer));
+        self
+    }
+
+    /// Use `authorizer` for calls requesting `scopes` with [`CallCredentials::scopes`].
+    #[must_use]
+    pub fn scopes<I, S>(
+        mut self,
+        scopes: I,
+        authorizer: impl Authorizer + Send + Sync + 'static,
+    ) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes.insert(
+            scopes.into_iter().map(Into::into).collect(),
+            Arc::new(authorizer),
+        );
+        self
+    }
+
+    /// Returns the authorizer for `request`.
+    fn select<T>(&self, request: &tonic::Request<T>) -> Result<DynAuthorizer, tonic::Status> {
+        let extensions = request.extensions();
+        match extensions.get::<CallCredentials>() {
+            Some(CallCredentials::Authorizer(authorizer)) => return Ok(authorizer.clone()),
+            Some(CallCredentials::Scopes(scopes)) => {
+                return self.scopes.get(scopes).cloned().ok_or_else(|| {
+                    tonic::Status::unauthenticated(format!(
+                        "No authorizer is configured for scopes {scopes:?}"
+                    ))
+                });
+            }
+            None => {}
+        }
+
+        let route = extensions
+            .get::<tonic::GrpcMethod<'static>>()
+            .and_then(|method| {
+                let service = format!("/{}/", method.service());
+                self.routes
+                    .get(&format!("{service}{}", method.method()))
+                    .or_else(|| self.routes.get(&format!("{service}*")))
+            });
+        route
+            .or(self.default.as_ref())
+            .cloned()
+            .ok_or_else(|| tonic::Status::unauthenticated(Error::NoAuthorization.to_string()))
+    }
+}
+
+impl Debug for GrpcAuthRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcAuthRouter")
+            .field("has_default", &self.default.is_some())
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .field("scopes", &self.scopes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl tonic::service::Interceptor for GrpcAuthRouter {
+    fn call(
+        &mut self,
+        request: tonic::Request<()>,
+    ) -> std::result::Result<tonic::Request<()>, tonic::Status> {
+        let authorizer = self.select(&request)?;
+        super::intercept(&*authorizer, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::service::Interceptor;
+
+    use super::*;
+    use crate::BearerTokenAuthorizer;
+
+    fn bearer(token: &str) -> BearerTokenAuthorizer {
+        BearerTokenAuthorizer::new(token).unwrap()
+    }
+
+    fn call(
+        router: &mut GrpcAuthRouter,
+        method: Option<(&'static str, &'static str)>,
+        credentials: Option<CallCredentials>,
+    ) -> Result<String, tonic::Code> {
+        let mut request = tonic::Request::new(());
+        if let Some((service, method)) = method {
+            request
+                .extensions_mut()
+                .insert(tonic::GrpcMethod::new(service, method));
+        }
+        if let Some(credentials) = credentials {
+            credentials.apply(&mut request);
+        }
+        let request = router.call(request).map_err(|status| status.code())?;
+        Ok(request
+            .metadata()
+            .get("authorization")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string())
+    }
+
+    #[test]
+    fn test_grpc_auth_router() {
+        let mut router = GrpcAuthRouter::with_default(bearer("default"))
+            .route("/admin.Admin/*", bearer("admin"))
+            .route("/admin.Admin/Read", bearer("reader"))
+            .scopes(["write", "read"], bearer("writer"));
+
+        let admin = Some(("admin.Admin", "Drop"));
+        assert_eq!(call(&mut router, None, None).unwrap(), "Bearer default");
+        assert_eq!(
+            call(&mut router, Some(("other.Other", "Drop")), None).unwrap(),
+            "Bearer default"
+        );
+        assert_eq!(call(&mut router, admin, None).unwrap(), "Bearer admin");
+        assert_eq!(
+            call(&mut router, Some(("admin.Admin", "Read")), None).unwrap(),
+            "Bearer reader"
+        );
+        assert_eq!(
+            call(
+                &mut router,
+                admin,
+                Some(CallCredentials::scopes(["read", "write"]))
+            )
+            .unwrap(),
+            "Bearer writer"
+        );
+        assert_eq!(
+            call(
+                &mut router,
+                admin,
+                Some(CallCredentials::authorizer(bearer("override")))
+            )
+            .unwrap(),
+            "Bearer override"
+        );
+        assert_eq!(
+            call(&mut router, admin, Some(CallCredentials::scopes(["read"]))),
+            Err(tonic::Code::Unauthenticated)
+        );
+
+        let mut router = GrpcAuthRouter::new().route("/admin.Admin/*", bearer("admin"));
+        assert_eq!(
+            call(&mut router, None, None),
+            Err(tonic::Code::Unauthenticated)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Health reporting for authorizers, for example to back readiness probes.
+use std::time::{Instant, SystemTime};
+
+use crate::error::Error;
+
+/// Whether an authorizer is currently able to authenticate requests.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum HealthStatus {
+    /// A valid token is available and the last refresh (if any) succeeded.
+    Healthy,
+    /// A valid token is still available, but refreshing it failed. Requests keep
+    /// working until the token expires.
+    Degraded { last_error: Error },
+    /// No valid token is available. Requests fail until a refresh succeeds.
+    Unhealthy { error: Error },
+}
+
+/// Snapshot of the health of an [`Authorizer`](super::Authorizer).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AuthorizerHealth {
+    /// Current status.
+    pub status: HealthStatus,
+    /// Wall-clock time of the last successful token fetch.
+    /// `None` for authorizers with static credentials.
+    pub last_refresh: Option<SystemTime>,
+    /// Number of refreshes that failed since the last successful one.
+    pub consecutive_failures: u32,
+    /// Time at which the next refresh is scheduled.
+    /// `None` if the authorizer does not refresh its credentials.
+    pub next_refresh: Option<Instant>,
+}
+
+impl AuthorizerHealth {
+    /// Create a health report with the given status, no refresh history and no
+    /// scheduled refresh.
+    #[must_use]
+    pub fn new(status: HealthStatus) -> Self {
+        Self {
+            status,
+            last_refresh: None,
+            consecutive_failures: 0,
+            next_refresh: None,
+        }
+    }
+
+    /// Returns `true` if the status is [`HealthStatus::Healthy`].
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        matches!(self.status, HealthStatus::Healthy)
+    }
+
+    /// Returns `true` if requests can currently be authorized, i.e. the status is
+    /// [`HealthStatus::Healthy`] or [`HealthStatus::Degraded`].
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        !matches!(self.status, HealthStatus::Unhealthy { .. })
+    }
+}
@@ -1,13 +1,77 @@
+mod any;
+mod api_key;
+mod basic_auth;
 mod bearer_token;
+#[cfg(feature = "blocking")]
+mod blocking_client_credentials;
 #[cfg(feature = "client-credentials")]
 mod client_credentials;
+mod composite;
+#[cfg(feature = "client-credentials")]
+mod credential_source;
+#[cfg(feature = "client-credentials")]
+mod events;
+#[cfg(feature = "tonic")]
+mod grpc_router;
+mod health;
+#[cfg(feature = "client-credentials")]
+mod refresh_driver;
+#[cfg(feature = "client-credentials")]
+mod shared;
+#[cfg(feature = "client-credentials")]
+mod token_store;
 
-use std::sync::Arc;
+use std::{future::Future, pin::Pin, sync::Arc};
 
+pub use any::AnyAuthorizer;
+pub use api_key::{ApiKeyAuthorizer, ApiKeyLocation};
+pub use basic_auth::BasicAuthAuthorizer;
 pub use bearer_token::*;
+#[cfg(feature = "blocking")]
+pub use blocking_client_credentials::{
+    BlockingClientCredentialAuthorizer, BlockingClientCredentialAuthorizerBuilder, RefreshThread,
+};
 #[cfg(feature = "client-credentials")]
 pub use client_credentials::*;
-use http::HeaderValue;
+pub use composite::{FallbackAuthorizer, FallbackSource, FirstHealthy, RoundRobinAuthorizer};
+#[cfg(feature = "client-credentials")]
+pub use credential_source::{
+    CredentialFuture, CredentialSource, EnvCredential, FileCredential, FnCredential,
+    StaticCredential,
+};
+#[cfg(feature = "client-credentials")]
+pub use events::AuthorizerEvent;
+#[cfg(feature = "tonic")]
+pub use grpc_router::{CallCredentials, GrpcAuthRouter};
+pub use health::{AuthorizerHealth, HealthStatus};
+use http::{HeaderName, HeaderValue, header::AUTHORIZATION};
+#[cfg(feature = "client-credentials")]
+pub use refresh_driver::RefreshDriver;
+#[cfg(feature = "token-store")]
+pub use token_store::FileTokenStore;
+#[cfg(feature = "client-credentials")]
+pub use token_store::{StoredToken, TokenCacheKey, TokenStore};
+
+/// Where an [`Authorizer`] places its credentials in a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CredentialLocation {
+    /// A header with the given name, `Authorization` for most authorizers.
+    Header(HeaderName),
+    /// A query parameter with the given name.
+    /// Not supported for gRPC requests.
+    Query(String),
+}
+
+impl Default for CredentialLocation {
+    fn default() -> Self {
+        Self::Header(AUTHORIZATION)
+    }
+}
+
+/// Boxed future returned by [`Authorizer::refresh`].
+pub type RefreshFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), crate::error::Error>> + Send + 'a>>;
 
 /// Main trait of this crate.
 pub trait Authorizer {
@@ -17,6 +81,48 @@ pub trait Authorizer {
     /// Fails if a token is not available, for example because the refresh failed.
     fn authorization_header(&self) -> Result<Arc<HeaderValue>, crate::error::Error>;
 
+    /// Returns the authorization header, or `None` if requests should be sent without
+    /// one, as done by [`AnyAuthorizer::None`]. Used by [`HttpClient`](crate::HttpClient).
+    ///
+    /// The default implementation wraps [`Self::authorization_header`].
+    ///
+    /// # Errors
+    /// Fails if a token is not available, for example because the refresh failed.
+    fn optional_authorization_header(
+        &self,
+    ) -> Result<Option<Arc<HeaderValue>>, crate::error::Error> {
+        self.authorization_header().map(Some)
+    }
+
+    /// Returns where the value of [`Self::authorization_header`] is placed in requests.
+    ///
+    /// The default implementation returns the `Authorization` header.
+    fn credential_location(&self) -> CredentialLocation {
+        CredentialLocation::default()
+    }
+
+    /// Returns the current health of the authorizer.
+    ///
+    /// The default implementation reports [`HealthStatus::Healthy`] if
+    /// [`Self::authorization_header`] succeeds and [`HealthStatus::Unhealthy`] otherwise.
+    fn health(&self) -> AuthorizerHealth {
+        match self.authorization_header() {
+            Ok(_) => AuthorizerHealth::new(HealthStatus::Healthy),
+            Err(error) => AuthorizerHealth::new(HealthStatus::Unhealthy { error }),
+        }
+    }
+
+    /// Fetch new credentials now, for example because the server rejected the current ones.
+    /// Used by [`GrpcAuthLayer`](crate::GrpcAuthLayer).
+    ///
+    /// The default implementation does nothing, which is correct for static credentials.
+    ///
+    /// # Errors
+    /// Fails if fetching new credentials fails.
+    fn refresh(&self) -> RefreshFuture<'_> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
     #[cfg(feature = "tonic")]
     /// Returns the authorization header to used for requests.
     ///
@@ -47,6 +153,68 @@ pub trait Authorizer {
     }
 }
 
+impl<T: Authorizer + ?Sized> Authorizer for Arc<T> {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>, crate::error::Error> {
+        (**self).authorization_header()
+    }
+
+    fn optional_authorization_header(
+        &self,
+    ) -> Result<Option<Arc<HeaderValue>>, crate::error::Error> {
+        (**self).optional_authorization_header()
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        (**self).credential_location()
+    }
+
+    fn health(&self) -> AuthorizerHealth {
+        (**self).health()
+    }
+
+    fn refresh(&self) -> RefreshFuture<'_> {
+        (**self).refresh()
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status> {
+        (**self).authorization_header_tonic()
+    }
+}
+
+impl<T: Authorizer + ?Sized> Authorizer for Box<T> {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>, crate::error::Error> {
+        (**self).authorization_header()
+    }
+
+    fn optional_authorization_header(
+        &self,
+    ) -> Result<Option<Arc<HeaderValue>>, crate::error::Error> {
+        (**self).optional_authorization_header()
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        (**self).credential_location()
+    }
+
+    fn health(&self) -> AuthorizerHealth {
+        (**self).health()
+    }
+
+    fn refresh(&self) -> RefreshFuture<'_> {
+        (**self).refresh()
+    }
+
+    #[cfg(feature = "tonic")]
+    fn authorization_header_tonic(
+        &self,
+    ) -> Result<tonic::metadata::MetadataValue<tonic::metadata::Ascii>, tonic::Status> {
+        (**self).authorization_header_tonic()
+    }
+}
+
 /// Helper function to ensure that a string is ASCII.
 ///
 /// # Errors
@@ -59,11 +227,11 @@ pub(crate) fn require_ascii(s: &str) -> Result<(), crate::error::Error> {
     }
 }
 
-/// Pre-computed representations of an `Authorization: Bearer <token>` header,
+/// Pre-computed representations of a credential header value,
 /// shared by the authorizers so the [`HeaderValue`] and (with the `tonic`
 /// feature) the `MetadataValue` are always built and marked sensitive the same
 /// way.
-pub(crate) struct BearerHeader {
+pub(crate) struct SensitiveHeader {
     pub(crate) header: Arc<HeaderValue>,
     #[cfg(feature = "tonic")]
     pub(crate) metadata: tonic::metadata::MetadataValue<tonic::metadata::Ascii>,
@@ -75,24 +243,58 @@ pub(crate) struct BearerHeader {
 /// # Errors
 /// Fails with `InvalidHeaderValue` if the resulting header value is invalid
 /// (for example non-ASCII).
-pub(crate) fn bearer_header(token: &str) -> Result<BearerHeader, crate::error::Error> {
-    let bearer = format!("Bearer {token}");
+pub(crate) fn bearer_header(token: &str) -> Result<SensitiveHeader, crate::error::Error> {
+    sensitive_header(&format!("Bearer {token}"))
+}
+
+/// Build the sensitive representations of an arbitrary header value.
+///
+/// # Errors
+/// Fails with `InvalidHeaderValue` if the header value is invalid
+/// (for example non-ASCII).
+pub(crate) fn sensitive_header(value: &str) -> Result<SensitiveHeader, crate::error::Error> {
     let mut header =
-        HeaderValue::from_str(&bearer).map_err(|_| crate::error::Error::InvalidHeaderValue)?;
+        HeaderValue::from_str(value).map_err(|_| crate::error::Error::InvalidHeaderValue)?;
     header.set_sensitive(true);
 
     #[cfg(feature = "tonic")]
     let metadata = {
         use std::str::FromStr;
-        let mut metadata = tonic::metadata::MetadataValue::from_str(&bearer)
+        let mut metadata = tonic::metadata::MetadataValue::from_str(value)
             .map_err(|_| crate::error::Error::InvalidHeaderValue)?;
         metadata.set_sensitive(true);
         metadata
     };
 
-    Ok(BearerHeader {
+    Ok(SensitiveHeader {
         header: Arc::new(header),
         #[cfg(feature = "tonic")]
         metadata,
     })
 }
+
+/// Shared implementation of the tonic interceptors: inserts the credentials of
+/// `authorizer` unless the request already carries them.
+///
+/// # Errors
+/// Fails if the authorizer fails to provide credentials, or if it uses a
+/// [`CredentialLocation`] that is not supported for gRPC.
+#[cfg(feature = "tonic")]
+pub(crate) fn intercept<A: Authorizer + ?Sized>(
+    authorizer: &A,
+    mut request: tonic::Request<()>,
+) -> Result<tonic::Request<()>, tonic::Status> {
+    let CredentialLocation::Header(name) = authorizer.credential_location() else {
+        return Err(tonic::Status::internal(
+            "Credentials in query parameters are not supported for gRPC requests",
+        ));
+    };
+    let key = tonic::metadata::AsciiMetadataKey::from_bytes(name.as_str().as_bytes())
+        .map_err(|e| tonic::Status::internal(format!("Invalid metadata key `{name}`: {e}")))?;
+
+    let metadata = request.metadata_mut();
+    if !metadata.contains_key(&key) {
+        metadata.insert(key, authorizer.authorization_header_tonic()?);
+    }
+    Ok(request)
+}
@@ -0,0 +1,172 @@
+//! Refresh loop driven by the caller instead of a spawned task.
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::Error,
+    runtime::{RuntimeFuture, TaskHandle},
+};
+
+/// Future running the refresh loop of a [`ClientCredentialAuthorizer`](crate::ClientCredentialAuthorizer),
+/// returned by [`ClientCredentialAuthorizerBuilder::build_with_driver`](crate::ClientCredentialAuthorizerBuilder::build_with_driver).
+///
+/// The token is only refreshed while the driver is polled, so it must be spawned
+/// or awaited by the caller, for example in a `JoinSet`. The driver completes with:
+/// * `Ok(())` if it was cancelled with a token passed to [`RefreshDriver::with_cancellation`],
+///   if the last handle to the authorizer was dropped, or if the token does not need to be refreshed.
+/// * [`Error::RefreshTaskFailed`] if the refresh loop panicked.
+///
+/// Dropping the driver stops the refresh loop.
+#[must_use = "the token is not refreshed unless the driver is polled"]
+pub struct RefreshDriver {
+    refresh: Option<RuntimeFuture<'static>>,
+    cancelled: Vec<RuntimeFuture<'static>>,
+    handle: DriverHandle,
+}
+
+impl RefreshDriver {
+    /// A driver without a refresh loop, which completes immediately.
+    pub(crate) fn new() -> Self {
+        let handle = DriverHandle {
+            cancel: CancellationToken::new(),
+            finished: Arc::new(AtomicBool::new(false)),
+        };
+        Self {
+            refresh: None,
+            cancelled: vec![Box::pin(handle.cancel.clone().cancelled_owned())],
+            handle,
+        }
+    }
+
+    /// Drive `refresh`. The returned handle cancels the driver when aborted.
+    pub(crate) fn drive(&mut self, refresh: RuntimeFuture<'static>) -> Box<dyn TaskHandle> {
+        self.refresh = Some(refresh);
+        Box::new(self.handle.clone())
+    }
+
+    /// Stop the refresh loop gracefully once `token` is cancelled.
+    /// Can be called multiple times to stop on any of several tokens.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancelled.push(Box::pin(token.cancelled_owned()));
+        self
+    }
+
+    fn finish(&mut self, result: Result<(), Error>) -> Poll<Result<(), Error>> {
+        self.refresh = None;
+        self.handle.finished.store(true, Ordering::SeqCst);
+        Poll::Ready(result)
+    }
+}
+
+impl Future for RefreshDriver {
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let Some(refresh) = this.refresh.as_mut() else {
+            return this.finish(Ok(()));
+        };
+
+        if this
+            .cancelled
+            .iter_mut()
+            .any(|cancelled| cancelled.as_mut().poll(cx).is_ready())
+        {
+            tracing::debug!("Refresh driver cancelled.");
+            return this.finish(Ok(()));
+        }
+
+        match std::panic::catch_unwind(AssertUnwindSafe(|| refresh.as_mut().poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(())) => this.finish(Ok(())),
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(ToString::to_string)
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                tracing::error!("Refresh loop panicked: {message}");
+                this.finish(Err(Error::RefreshTaskFailed(message)))
+            }
+        }
+    }
+}
+
+impl Drop for RefreshDriver {
+    fn drop(&mut self) {
+        self.handle.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+impl std::fmt::Debug for RefreshDriver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RefreshDriver")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`TaskHandle`] of a [`RefreshDriver`], held by the authorizer.
+#[derive(Debug, Clone)]
+struct DriverHandle {
+    cancel: CancellationToken,
+    finished: Arc<AtomicBool>,
+}
+
+impl TaskHandle for DriverHandle {
+    fn abort(&self) {
+        self.cancel.cancel();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_driver_cancellation() {
+        let token = CancellationToken::new();
+        let mut driver = RefreshDriver::new().with_cancellation(token.clone());
+        let handle = driver.drive(Box::pin(std::future::pending()));
+
+        let task = tokio::spawn(driver);
+        assert!(!handle.is_finished());
+        token.cancel();
+        assert!(task.await.unwrap().is_ok());
+        assert!(handle.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_driver_reports_panic() {
+        let mut driver = RefreshDriver::new();
+        let handle = driver.drive(Box::pin(async { panic!("refresh failed") }));
+
+        let result = driver.await;
+        assert!(
+            matches!(result, Err(Error::RefreshTaskFailed(message)) if message == "refresh failed")
+        );
+        assert!(handle.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_driver_stops_when_handle_aborted() {
+        let mut driver = RefreshDriver::new();
+        let handle = driver.drive(Box::pin(std::future::pending()));
+        handle.abort();
+        assert!(driver.await.is_ok());
+    }
+}
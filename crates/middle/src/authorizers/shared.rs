@@ -0,0 +1,72 @@
+//! Process-wide registry of authorizers, so that equivalent authorizers built
+//! independently share their token and refresh task.
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use super::token_store::TokenCacheKey;
+
+/// Non-owning handle to a shared authorizer.
+/// The registry never keeps an authorizer alive by itself.
+pub(crate) trait WeakHandle: Send + Sync + 'static {
+    type Strong;
+
+    /// Returns a new owning handle if the authorizer is still alive.
+    fn upgrade(&self) -> Option<Self::Strong>;
+}
+
+/// Type-erased registry entry.
+trait Entry: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn is_alive(&self) -> bool;
+}
+
+impl<W: WeakHandle> Entry for W {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_alive(&self) -> bool {
+        self.upgrade().is_some()
+    }
+}
+
+type Entries = HashMap<(TypeId, TokenCacheKey), Box<dyn Entry>>;
+
+static REGISTRY: LazyLock<Mutex<Entries>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn upgrade_entry<W: WeakHandle>(
+    entries: &Entries,
+    key: &(TypeId, TokenCacheKey),
+) -> Option<W::Strong> {
+    entries
+        .get(key)
+        .and_then(|entry| entry.as_any().downcast_ref::<W>())
+        .and_then(WeakHandle::upgrade)
+}
+
+/// Look up a live authorizer registered for `key`.
+pub(crate) fn lookup<W: WeakHandle>(key: &TokenCacheKey) -> Option<W::Strong> {
+    let entries = REGISTRY.lock().expect("Non-poisoned lock");
+    upgrade_entry::<W>(&entries, &(TypeId::of::<W>(), key.clone()))
+}
+
+/// Register `handle` for `key`.
+///
+/// If another live authorizer was registered for `key` in the meantime, it is
+/// kept and returned instead, so that concurrent builds converge on one instance.
+pub(crate) fn register<W: WeakHandle>(key: TokenCacheKey, handle: W) -> Option<W::Strong> {
+    let mut entries = REGISTRY.lock().expect("Non-poisoned lock");
+    let registry_key = (TypeId::of::<W>(), key);
+
+    if let Some(existing) = upgrade_entry::<W>(&entries, &registry_key) {
+        return Some(existing);
+    }
+
+    entries.insert(registry_key, Box::new(handle));
+    // Drop the entries of authorizers that are gone.
+    entries.retain(|_, entry| entry.is_alive());
+    None
+}
@@ -0,0 +1,509 @@
+//! Persistence of client credential tokens across process restarts.
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    time::{Duration, SystemTime},
+};
+
+use crate::error::Error;
+
+/// Storage for tokens fetched by a [`ClientCredentialAuthorizer`](super::ClientCredentialAuthorizer),
+/// so that short-lived processes can reuse a token instead of fetching a new one on
+/// every start.
+///
+/// Register a store with
+/// [`ClientCredentialAuthorizerBuilder::token_store`](super::ClientCredentialAuthorizerBuilder::token_store).
+/// `build()` loads an unexpired token from the store before contacting the Identity
+/// Provider, and every newly fetched token is written back.
+///
+/// Methods are called from async contexts and should return quickly.
+pub trait TokenStore: Debug + Send + Sync {
+    /// Load the token stored for `key`, if any.
+    /// Expired tokens may be returned, they are ignored by the caller.
+    ///
+    /// # Errors
+    /// Fails if the store cannot be read. Errors are logged and treated as a cache miss.
+    fn load(&self, key: &TokenCacheKey) -> Result<Option<StoredToken>, Error>;
+
+    /// Store `token` for `key`, replacing any previously stored token.
+    ///
+    /// # Errors
+    /// Fails if the store cannot be written. Errors are logged and otherwise ignored.
+    fn store(&self, key: &TokenCacheKey, token: &StoredToken) -> Result<(), Error>;
+}
+
+/// Identifies the tokens of one client configuration.
+/// Tokens are only reused for the same token url, client id, scopes and extra parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenCacheKey {
+    token_url: String,
+    client_id: String,
+    scopes: Vec<String>,
+    extra_params: BTreeMap<String, String>,
+}
+
+impl TokenCacheKey {
+    /// Create a new key. The order of `scopes` does not matter.
+    #[must_use]
+    pub fn new(
+        token_url: &str,
+        client_id: &str,
+        scopes: impl IntoIterator<Item = impl Into<String>>,
+        extra_params: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        let mut scopes = scopes.into_iter().map(Into::into).collect::<Vec<_>>();
+        scopes.sort_unstable();
+        scopes.dedup();
+        Self {
+            token_url: token_url.to_string(),
+            client_id: client_id.to_string(),
+            scopes,
+            extra_params: extra_params
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+
+    /// The token url.
+    #[must_use]
+    pub fn token_url(&self) -> &str {
+        &self.token_url
+    }
+
+    /// The client id.
+    #[must_use]
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// The requested scopes, sorted.
+    #[must_use]
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    /// The extra parameters of the token request.
+    #[must_use]
+    pub fn extra_params(&self) -> &BTreeMap<String, String> {
+        &self.extra_params
+    }
+
+    /// Stable, filesystem-safe fingerprint of the key (hex-encoded SHA-256).
+    #[cfg(feature = "token-store")]
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        use std::fmt::Write;
+
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        // Length-prefix every component so that different keys can't collide by
+        // shifting characters between components.
+        let mut update = |value: &str| {
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value.as_bytes());
+        };
+        update(&self.token_url);
+        update(&self.client_id);
+        for scope in &self.scopes {
+            update(scope);
+        }
+        update("");
+        for (name, value) in &self.extra_params {
+            update(name);
+            update(value);
+        }
+
+        hasher
+            .finalize()
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, byte| {
+                write!(hex, "{byte:02x}").expect("Writing to a String never fails");
+                hex
+            })
+    }
+}
+
+/// A token as persisted by a [`TokenStore`].
+///
+/// The expiry is stored as wall-clock time, as monotonic [`Instant`](std::time::Instant)s
+/// cannot be compared across processes.
+#[derive(Clone, PartialEq, Eq, veil::Redact)]
+pub struct StoredToken {
+    /// The access token, without the `Bearer` prefix.
+    #[redact]
+    pub access_token: String,
+    /// Wall-clock expiry of the token. `None` if the token does not expire.
+    pub expires_at: Option<SystemTime>,
+}
+
+impl StoredToken {
+    /// Remaining lifetime of the token. `Some(Duration::ZERO)` if it has expired,
+    /// `None` if it does not expire.
+    #[must_use]
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_in_at(SystemTime::now())
+    }
+
+    /// Remaining lifetime of the token as of `now`. See [`StoredToken::expires_in`].
+    #[must_use]
+    pub fn expires_in_at(&self, now: SystemTime) -> Option<Duration> {
+        self.expires_at
+            .map(|expires_at| expires_at.duration_since(now).unwrap_or(Duration::ZERO))
+    }
+
+    /// Returns `true` if the token has a known expiry that has already passed.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Returns `true` if the token has a known expiry that has passed at `now`.
+    #[must_use]
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expires_in_at(now).is_some_and(|d| d.is_zero())
+    }
+}
+
+#[cfg(feature = "token-store")]
+pub use file::FileTokenStore;
+
+#[cfg(feature = "token-store")]
+mod file {
+    use std::{
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicU64, Ordering},
+        time::{Duration, SystemTime},
+    };
+
+    use super::{StoredToken, TokenCacheKey, TokenStore};
+    use crate::error::Error;
+
+    /// Distinguishes the temporary files of concurrent writes within this process.
+    static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    /// [`TokenStore`] that keeps one file per [`TokenCacheKey`] in a directory.
+    ///
+    /// On Unix, the directory is created with mode `0700` and token files with mode
+    /// `0600`, so that only the current user can read them. Files are replaced
+    /// atomically.
+    ///
+    /// With the `token-store-encryption` feature, tokens can additionally be
+    /// encrypted with AES-256-GCM, see [`FileTokenStore::with_encryption_key`].
+    #[derive(Clone)]
+    pub struct FileTokenStore {
+        directory: PathBuf,
+        #[cfg(feature = "token-store-encryption")]
+        encryption_key: Option<[u8; 32]>,
+    }
+
+    impl std::fmt::Debug for FileTokenStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let mut debug = f.debug_struct("FileTokenStore");
+            debug.field("directory", &self.directory);
+            #[cfg(feature = "token-store-encryption")]
+            debug.field("encrypted", &self.encryption_key.is_some());
+            debug.finish()
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct TokenFile {
+        access_token: String,
+        /// Seconds since the Unix epoch.
+        expires_at: Option<u64>,
+    }
+
+    impl FileTokenStore {
+        /// Create a store that keeps tokens in `directory`.
+        /// The directory is created on the first write if it does not exist.
+        #[must_use]
+        pub fn new(directory: impl Into<PathBuf>) -> Self {
+            Self {
+                directory: directory.into(),
+                #[cfg(feature = "token-store-encryption")]
+                encryption_key: None,
+            }
+        }
+
+        /// Encrypt stored tokens with the given AES-256 key.
+        /// Files that cannot be decrypted with the key are treated as missing.
+        #[cfg(feature = "token-store-encryption")]
+        #[must_use]
+        pub fn with_encryption_key(mut self, key: [u8; 32]) -> Self {
+            self.encryption_key = Some(key);
+            self
+        }
+
+        /// The directory tokens are stored in.
+        #[must_use]
+        pub fn directory(&self) -> &Path {
+            &self.directory
+        }
+
+        fn path(&self, key: &TokenCacheKey) -> PathBuf {
+            self.directory.join(format!("{}.token", key.fingerprint()))
+        }
+
+        #[cfg(feature = "token-store-encryption")]
+        fn seal(&self, key: &TokenCacheKey, plaintext: Vec<u8>) -> Result<Vec<u8>, Error> {
+            use aes_gcm::{
+                Aes256Gcm, KeyInit,
+                aead::{Aead, AeadCore, OsRng, Payload},
+            };
+
+            let Some(encryption_key) = &self.encryption_key else {
+                return Ok(plaintext);
+            };
+            let cipher = Aes256Gcm::new(encryption_key.into());
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            // Bind the ciphertext to the key so a file can't be swapped for another.
+            let aad = key.fingerprint();
+            let ciphertext = cipher
+                .encrypt(
+                    &nonce,
+                    Payload {
+                        msg: &plaintext,
+                        aad: aad.as_bytes(),
+                    },
+                )
+                .map_err(|e| Error::TokenStore(format!("Failed to encrypt token: {e}")))?;
+            Ok([nonce.as_slice(), &ciphertext].concat())
+        }
+
+        #[cfg(not(feature = "token-store-encryption"))]
+        #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+        fn seal(&self, _key: &TokenCacheKey, plaintext: Vec<u8>) -> Result<Vec<u8>, Error> {
+            Ok(plaintext)
+        }
+
+        #[cfg(feature = "token-store-encryption")]
+        fn open(&self, key: &TokenCacheKey, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+            use aes_gcm::{
+                Aes256Gcm, KeyInit, Nonce,
+                aead::{Aead, Payload},
+            };
+
+            const NONCE_LEN: usize = 12;
+
+            let Some(encryption_key) = &self.encryption_key else {
+                return Ok(data);
+            };
+            if data.len() < NONCE_LEN {
+                return Err(Error::TokenStore(
+                    "Encrypted token is truncated".to_string(),
+                ));
+            }
+            let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+            let aad = key.fingerprint();
+            Aes256Gcm::new(encryption_key.into())
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: aad.as_bytes(),
+                    },
+                )
+                .map_err(|e| Error::TokenStore(format!("Failed to decrypt token: {e}")))
+        }
+
+        #[cfg(not(feature = "token-store-encryption"))]
+        #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+        fn open(&self, _key: &TokenCacheKey, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+            Ok(data)
+        }
+
+        fn create_directory(&self) -> std::io::Result<()> {
+            let mut builder = fs::DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            builder.create(&self.directory)
+        }
+    }
+
+    impl TokenStore for FileTokenStore {
+        fn load(&self, key: &TokenCacheKey) -> Result<Option<StoredToken>, Error> {
+            let path = self.path(key);
+            let data = match fs::read(&path) {
+                Ok(data) => data,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => {
+                    return Err(Error::TokenStore(format!(
+                        "Failed to read `{}`: {e}",
+                        path.display()
+                    )));
+                }
+            };
+            let file: TokenFile = serde_json::from_slice(&self.open(key, data)?)
+                .map_err(|e| Error::TokenStore(format!("Failed to parse stored token: {e}")))?;
+
+            Ok(Some(StoredToken {
+                access_token: file.access_token,
+                expires_at: file
+                    .expires_at
+                    .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+            }))
+        }
+
+        fn store(&self, key: &TokenCacheKey, token: &StoredToken) -> Result<(), Error> {
+            let file = TokenFile {
+                access_token: token.access_token.clone(),
+                expires_at: token.expires_at.map(|expires_at| {
+                    expires_at
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs()
+                }),
+            };
+            let data = serde_json::to_vec(&file)
+                .map_err(|e| Error::TokenStore(format!("Failed to serialize token: {e}")))?;
+            let data = self.seal(key, data)?;
+
+            let path = self.path(key);
+            let io_error = |e: std::io::Error| {
+                Error::TokenStore(format!("Failed to write `{}`: {e}", path.display()))
+            };
+            self.create_directory().map_err(io_error)?;
+
+            // Write to a temporary file first and rename it, so that concurrent
+            // readers never observe a partially written token.
+            let tmp_path = path.with_extension(format!(
+                "token.{}.{}.tmp",
+                std::process::id(),
+                TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let write = || -> std::io::Result<()> {
+                let mut tmp_file = options.open(&tmp_path)?;
+                tmp_file.write_all(&data)?;
+                tmp_file.sync_all()?;
+                fs::rename(&tmp_path, &path)
+            };
+            write().map_err(|e| {
+                fs::remove_file(&tmp_path).ok();
+                io_error(e)
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn key() -> TokenCacheKey {
+            TokenCacheKey::new(
+                "https://idp.example.com/token",
+                "my-client",
+                ["b", "a"],
+                [("audience", "api")],
+            )
+        }
+
+        #[test]
+        fn test_roundtrip() {
+            let dir = tempfile::tempdir().unwrap();
+            let store = FileTokenStore::new(dir.path().join("tokens"));
+            assert!(store.load(&key()).unwrap().is_none());
+
+            let token = StoredToken {
+                access_token: "my-token".to_string(),
+                expires_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(4_000_000_000)),
+            };
+            store.store(&key(), &token).unwrap();
+            assert_eq!(store.load(&key()).unwrap(), Some(token));
+
+            // Scope order does not matter, other scopes do.
+            let reordered = TokenCacheKey::new(
+                "https://idp.example.com/token",
+                "my-client",
+                ["a", "b"],
+                [("audience", "api")],
+            );
+            assert!(store.load(&reordered).unwrap().is_some());
+            let other = TokenCacheKey::new(
+                "https://idp.example.com/token",
+                "my-client",
+                ["a"],
+                [("audience", "api")],
+            );
+            assert!(store.load(&other).unwrap().is_none());
+        }
+
+        #[test]
+        fn test_concurrent_stores() {
+            let dir = tempfile::tempdir().unwrap();
+            let store = FileTokenStore::new(dir.path());
+            let tokens: Vec<_> = (0..8)
+                .map(|i| StoredToken {
+                    access_token: format!("token-{i}-{}", "x".repeat(10_000)),
+                    expires_at: None,
+                })
+                .collect();
+
+            std::thread::scope(|scope| {
+                for token in &tokens {
+                    scope.spawn(|| {
+                        for _ in 0..10 {
+                            store.store(&key(), token).unwrap();
+                        }
+                    });
+                }
+            });
+            let stored = store.load(&key()).unwrap().unwrap();
+            assert!(tokens.contains(&stored));
+            // No temporary files are left behind.
+            assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn test_permissions() {
+            use std::os::unix::fs::PermissionsExt;
+
+            let dir = tempfile::tempdir().unwrap();
+            let store = FileTokenStore::new(dir.path().join("tokens"));
+            let token = StoredToken {
+                access_token: "my-token".to_string(),
+                expires_at: None,
+            };
+            store.store(&key(), &token).unwrap();
+
+            let dir_mode = fs::metadata(store.directory())
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(dir_mode & 0o777, 0o700);
+            let file_mode = fs::metadata(store.path(&key()))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(file_mode & 0o777, 0o600);
+        }
+
+        #[cfg(feature = "token-store-encryption")]
+        #[test]
+        fn test_encryption() {
+            let dir = tempfile::tempdir().unwrap();
+            let store = FileTokenStore::new(dir.path()).with_encryption_key([7; 32]);
+            let token = StoredToken {
+                access_token: "my-token".to_string(),
+                expires_at: None,
+            };
+            store.store(&key(), &token).unwrap();
+
+            let raw = fs::read(store.path(&key())).unwrap();
+            assert!(!String::from_utf8_lossy(&raw).contains("my-token"));
+            assert_eq!(store.load(&key()).unwrap(), Some(token));
+
+            let wrong_key = FileTokenStore::new(dir.path()).with_encryption_key([8; 32]);
+            assert!(wrong_key.load(&key()).is_err());
+        }
+    }
+}
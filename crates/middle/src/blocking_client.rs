@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use http::HeaderValue;
+use reqwest::IntoUrl;
+
+use crate::{
+    Authorizer, CredentialLocation,
+    client::query_value,
+    error::{Error, Result},
+};
+
+/// Wrapper around `reqwest::blocking::Client` that automatically adds the authorization header,
+/// while keeping it up-to-date using an `Authorizer`.
+///
+/// Blocking counterpart of [`HttpClient`](crate::HttpClient), for example to be used with a
+/// [`BlockingClientCredentialAuthorizer`](crate::BlockingClientCredentialAuthorizer).
+/// Like `reqwest::blocking::Client`, it must not be used within an async runtime.
+///
+/// ## `OpenTelemetry`
+/// If the `opentelemetry` feature is enabled, the context of the current `tracing` span
+/// is injected into every request using the globally registered propagator. [`BlockingHttpClient::execute`]
+/// additionally records a client span per request with the HTTP semantic-convention attributes.
+#[derive(Debug, Clone)]
+pub struct BlockingHttpClient<A: Authorizer> {
+    authorizer: A,
+    client: reqwest::blocking::Client,
+}
+
+impl<A: Authorizer> BlockingHttpClient<A> {
+    /// Creates a new `BlockingHttpClient` with the given `Authorizer`.
+    ///
+    /// # Panics
+    /// Panics if called within an async runtime, see `reqwest::blocking::Client::new`.
+    pub fn new(authorizer: A) -> Self {
+        Self {
+            authorizer,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Set a custom `reqwest::blocking::Client`.
+    #[must_use]
+    pub fn set_client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Obtain the currently used authorization header.
+    ///
+    /// # Errors
+    /// Returns an error if the authorizer fails to provide a token, typically because
+    /// the token refresh failed.
+    pub fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        self.authorizer.authorization_header()
+    }
+
+    /// Start building a `Request`, adding the authorization header.
+    ///
+    /// # Errors
+    /// Returns an error if the authorizer fails to provide a token, typically because
+    /// the token refresh failed.
+    pub fn request<U: IntoUrl>(
+        &self,
+        method: reqwest::Method,
+        url: U,
+    ) -> Result<reqwest::blocking::RequestBuilder> {
+        let header = self.authorizer.optional_authorization_header()?;
+        let mut request = self.client.request(method, url);
+        if let Some(header) = header {
+            request = match self.authorizer.credential_location() {
+                CredentialLocation::Header(name) => {
+                    request.header(name, Arc::unwrap_or_clone(header))
+                }
+                CredentialLocation::Query(name) => request.query(&[(name, query_value(&header)?)]),
+            };
+        }
+        #[cfg(feature = "opentelemetry")]
+        let request = {
+            let mut headers = http::HeaderMap::new();
+            crate::otel::inject_context(&mut headers);
+            request.headers(headers)
+        };
+        Ok(request)
+    }
+
+    /// Execute a `Request`, adding the authorization header if it is not already set.
+    ///
+    /// # Errors
+    /// - Returns an error if the authorizer fails to provide a token, typically because the token refresh failed.
+    /// - Returns an error if the request fails.
+    pub fn execute(
+        &self,
+        mut request: reqwest::blocking::Request,
+    ) -> Result<reqwest::blocking::Response> {
+        let header = self.authorizer.optional_authorization_header()?;
+
+        if let Some(header) = header {
+            match self.authorizer.credential_location() {
+                CredentialLocation::Header(name) => {
+                    if !request.headers().contains_key(&name) {
+                        request
+                            .headers_mut()
+                            .insert(name, Arc::unwrap_or_clone(header));
+                    }
+                }
+                CredentialLocation::Query(name) => {
+                    let url = request.url_mut();
+                    if !url.query_pairs().any(|(key, _)| key == name.as_str()) {
+                        url.query_pairs_mut()
+                            .append_pair(&name, query_value(&header)?);
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        let response = {
+            let credential_query = match self.authorizer.credential_location() {
+                CredentialLocation::Query(name) => Some(name),
+                CredentialLocation::Header(_) => None,
+            };
+            let span = crate::otel::http_client_span(
+                request.method(),
+                request.url(),
+                credential_query.as_deref(),
+            );
+            let _entered = span.enter();
+            crate::otel::inject_context(request.headers_mut());
+            let response = self.client.execute(request);
+            crate::otel::record_response(
+                &span,
+                response.as_ref().map(reqwest::blocking::Response::status),
+            );
+            response
+        };
+        #[cfg(not(feature = "opentelemetry"))]
+        let response = self.client.execute(request);
+
+        response.map_err(|e| Error::from(Arc::new(e)))
+    }
+
+    /// Convenience method to make a `GET` request to a URL.
+    ///
+    /// # Errors
+    /// See [`request`](Self::request).
+    pub fn get<U: IntoUrl>(&self, url: U) -> Result<reqwest::blocking::RequestBuilder> {
+        self.request(reqwest::Method::GET, url)
+    }
+
+    /// Convenience method to make a `POST` request to a URL.
+    ///
+    /// # Errors
+    /// See [`request`](Self::request).
+    pub fn post<U: IntoUrl>(&self, url: U) -> Result<reqwest::blocking::RequestBuilder> {
+        self.request(reqwest::Method::POST, url)
+    }
+
+    /// Convenience method to make a `PUT` request to a URL.
+    ///
+    /// # Errors
+    /// See [`request`](Self::request).
+    pub fn put<U: IntoUrl>(&self, url: U) -> Result<reqwest::blocking::RequestBuilder> {
+        self.request(reqwest::Method::PUT, url)
+    }
+
+    /// Convenience method to make a `PATCH` request to a URL.
+    ///
+    /// # Errors
+    /// See [`request`](Self::request).
+    pub fn patch<U: IntoUrl>(&self, url: U) -> Result<reqwest::blocking::RequestBuilder> {
+        self.request(reqwest::Method::PATCH, url)
+    }
+
+    /// Convenience method to make a `DELETE` request to a URL.
+    ///
+    /// # Errors
+    /// See [`request`](Self::request).
+    pub fn delete<U: IntoUrl>(&self, url: U) -> Result<reqwest::blocking::RequestBuilder> {
+        self.request(reqwest::Method::DELETE, url)
+    }
+
+    /// Convenience method to make a `HEAD` request to a URL.
+    ///
+    /// # Errors
+    /// See [`request`](Self::request).
+    pub fn head<U: IntoUrl>(&self, url: U) -> Result<reqwest::blocking::RequestBuilder> {
+        self.request(reqwest::Method::HEAD, url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApiKeyAuthorizer, BearerTokenAuthorizer};
+
+    #[test]
+    fn test_blocking_http_client() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/get")
+            .match_header("authorization", "Bearer test")
+            .with_status(200)
+            .expect(2)
+            .create();
+
+        let client = BlockingHttpClient::new(BearerTokenAuthorizer::new("test").unwrap());
+        let url = format!("{}/get", server.url());
+        let response = client.get(&url).unwrap().send().unwrap();
+        assert!(response.status().is_success());
+        let request = reqwest::blocking::Client::new().get(&url).build().unwrap();
+        client.execute(request).unwrap();
+
+        mock.assert();
+    }
+
+    #[test]
+    fn test_blocking_http_client_query() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/query")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "api_key".to_string(),
+                "my-key".to_string(),
+            ))
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(200)
+            .create();
+
+        let client = BlockingHttpClient::new(ApiKeyAuthorizer::query("api_key", "my-key").unwrap());
+        client
+            .get(format!("{}/query", server.url()))
+            .unwrap()
+            .send()
+            .unwrap();
+
+        mock.assert();
+    }
+}
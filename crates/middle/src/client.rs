@@ -1,27 +1,70 @@
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc, time::Duration};
 
-use http::{HeaderValue, header::AUTHORIZATION};
+use http::{HeaderMap, HeaderValue};
 use reqwest::IntoUrl;
 
-use crate::{Authorizer, error::Result};
+use crate::{
+    AuthorizedRequestBuilder, Authorizer, CredentialLocation, OriginPolicy,
+    error::{Error, Result},
+};
 
 /// Wrapper around `reqwest::Client` that automatically adds the authorization header,
 /// while keeping it up-to-date using an `Authorizer`.
 ///
-/// Designed to be a mostly drop-in replacement for `reqwest::Client`.
-#[derive(Debug, Clone)]
+/// The credentials are placed as given by [`Authorizer::credential_location`]:
+/// usually in the `Authorization` header, or in a custom header or query parameter.
+///
+/// Designed to be a mostly drop-in replacement for `reqwest::Client`: configure it with
+/// [`HttpClient::builder`] instead of `reqwest::Client::builder`, or wrap an existing
+/// client with `HttpClient::from((client, authorizer))`. Cloning is cheap and does not
+/// require the authorizer to be `Clone`, as both the client and the authorizer are shared.
+///
+/// ## Origin policy
+/// By default, credentials are added to requests to any URL. Restrict this with an
+/// [`OriginPolicy`], set with [`HttpClientBuilder::origin_policy`] or
+/// [`HttpClient::set_origin_policy`], so tokens are not leaked to foreign hosts.
+///
+/// ## `OpenTelemetry`
+/// If the `opentelemetry` feature is enabled, the context of the current `tracing` span
+/// is injected into every request using the globally registered propagator (for example
+/// W3C `traceparent` / `tracestate`), and a client span is recorded per request with the
+/// HTTP semantic-convention attributes.
+#[derive(Debug)]
 pub struct HttpClient<A: Authorizer> {
-    authorizer: A,
+    authorizer: Arc<A>,
     client: reqwest::Client,
+    origin_policy: Option<Arc<OriginPolicy>>,
+}
+
+impl<A: Authorizer> Clone for HttpClient<A> {
+    fn clone(&self) -> Self {
+        Self {
+            authorizer: self.authorizer.clone(),
+            client: self.client.clone(),
+            origin_policy: self.origin_policy.clone(),
+        }
+    }
+}
+
+impl<A: Authorizer> From<(reqwest::Client, A)> for HttpClient<A> {
+    fn from((client, authorizer): (reqwest::Client, A)) -> Self {
+        Self {
+            authorizer: Arc::new(authorizer),
+            client,
+            origin_policy: None,
+        }
+    }
 }
 
 impl<A: Authorizer> HttpClient<A> {
     /// Creates a new `HttpClient` with the given `Authorizer`.
     pub fn new(authorizer: A) -> Self {
-        Self {
-            authorizer,
-            client: reqwest::Client::new(),
-        }
+        Self::from((reqwest::Client::new(), authorizer))
+    }
+
+    /// Creates an [`HttpClientBuilder`] to configure the underlying `reqwest::Client`.
+    pub fn builder(authorizer: A) -> HttpClientBuilder<A> {
+        HttpClientBuilder::new(authorizer)
     }
 
     /// Set a custom `reqwest::Client`.
@@ -31,6 +74,30 @@ impl<A: Authorizer> HttpClient<A> {
         self
     }
 
+    /// Only add credentials to requests to URLs allowed by `policy`.
+    ///
+    /// Unlike [`HttpClientBuilder::origin_policy`], this does not change how redirects
+    /// are followed. Configure the client with [`OriginPolicy::same_origin_redirects`]
+    /// to not follow cross-origin redirects.
+    #[must_use]
+    pub fn set_origin_policy(mut self, policy: OriginPolicy) -> Self {
+        self.origin_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// The underlying `reqwest::Client`. Requests sent through it directly do not
+    /// carry any credentials.
+    #[must_use]
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// The `Authorizer` providing the credentials.
+    #[must_use]
+    pub fn authorizer(&self) -> &A {
+        &self.authorizer
+    }
+
     /// Obtain the currently used authorization header.
     ///
     /// # Errors
@@ -40,22 +107,14 @@ impl<A: Authorizer> HttpClient<A> {
         self.authorizer.authorization_header()
     }
 
-    /// Start building a `Request`, adding the authorization header.
-    ///
-    /// # Errors
-    /// Returns an error if the authorizer fails to provide a token, typically because
-    /// the token refresh failed.
+    /// Start building a `Request`. The authorization header is added when the
+    /// request is sent, see [`AuthorizedRequestBuilder`].
     pub fn request<U: IntoUrl>(
         &self,
         method: reqwest::Method,
         url: U,
-    ) -> Result<reqwest::RequestBuilder> {
-        let header = self.authorization_header()?;
-        let request = self
-            .client
-            .request(method, url)
-            .header(AUTHORIZATION, Arc::unwrap_or_clone(header));
-        Ok(request)
+    ) -> AuthorizedRequestBuilder<A> {
+        AuthorizedRequestBuilder::new(self.clone(), self.client.request(method, url))
     }
 
     /// Execute a `Request`, adding the authorization header if it is not already set.
@@ -64,69 +123,240 @@ impl<A: Authorizer> HttpClient<A> {
     /// - Returns an error if the authorizer fails to provide a token, typically because the token refresh failed.
     /// - Returns an error if the request fails.
     pub async fn execute(&self, mut request: reqwest::Request) -> Result<reqwest::Response> {
-        let header = self.authorization_header()?;
+        self.authorize(&mut request)?;
+        self.send(request).await
+    }
+
+    /// Add the credentials of the authorizer to `request`, unless it already carries them
+    /// or the origin policy does not allow them.
+    pub(crate) fn authorize(&self, request: &mut reqwest::Request) -> Result<()> {
+        if let Some(policy) = &self.origin_policy
+            && !policy.check(request.url())?
+        {
+            return Ok(());
+        }
+        let Some(header) = self.authorizer.optional_authorization_header()? else {
+            return Ok(());
+        };
 
-        if !request.headers().contains_key(AUTHORIZATION) {
-            request
-                .headers_mut()
-                .insert(AUTHORIZATION, Arc::unwrap_or_clone(header));
+        match self.authorizer.credential_location() {
+            CredentialLocation::Header(name) => {
+                if !request.headers().contains_key(&name) {
+                    request
+                        .headers_mut()
+                        .insert(name, Arc::unwrap_or_clone(header));
+                }
+            }
+            CredentialLocation::Query(name) => {
+                let url = request.url_mut();
+                if !url.query_pairs().any(|(key, _)| key == name.as_str()) {
+                    url.query_pairs_mut()
+                        .append_pair(&name, query_value(&header)?);
+                }
+            }
         }
-        self.client
-            .execute(request)
+        Ok(())
+    }
+
+    /// Send `request` as is.
+    pub(crate) async fn send(&self, request: reqwest::Request) -> Result<reqwest::Response> {
+        #[cfg(feature = "opentelemetry")]
+        let response = {
+            use tracing::Instrument;
+
+            let mut request = request;
+            let credential_query = match self.authorizer.credential_location() {
+                CredentialLocation::Query(name) => Some(name),
+                CredentialLocation::Header(_) => None,
+            };
+            let span = crate::otel::http_client_span(
+                request.method(),
+                request.url(),
+                credential_query.as_deref(),
+            );
+            async {
+                crate::otel::inject_context(request.headers_mut());
+                let response = self.client.execute(request).await;
+                crate::otel::record_response(
+                    &tracing::Span::current(),
+                    response.as_ref().map(reqwest::Response::status),
+                );
+                response
+            }
+            .instrument(span)
             .await
-            .map_err(Arc::new)
-            .map_err(Into::into)
+        };
+        #[cfg(not(feature = "opentelemetry"))]
+        let response = self.client.execute(request).await;
+
+        response.map_err(Arc::new).map_err(Into::into)
     }
 
     /// Convenience method to make a `GET` request to a URL.
-    ///
-    /// # Errors
-    /// See [`request`](Self::request).
-    pub fn get<U: IntoUrl>(&self, url: U) -> Result<reqwest::RequestBuilder> {
+    pub fn get<U: IntoUrl>(&self, url: U) -> AuthorizedRequestBuilder<A> {
         self.request(reqwest::Method::GET, url)
     }
 
     /// Convenience method to make a `POST` request to a URL.
-    ///
-    /// # Errors
-    /// See [`request`](Self::request).
-    pub fn post<U: IntoUrl>(&self, url: U) -> Result<reqwest::RequestBuilder> {
+    pub fn post<U: IntoUrl>(&self, url: U) -> AuthorizedRequestBuilder<A> {
         self.request(reqwest::Method::POST, url)
     }
 
     /// Convenience method to make a `PUT` request to a URL.
-    ///
-    /// # Errors
-    /// See [`request`](Self::request).
-    pub fn put<U: IntoUrl>(&self, url: U) -> Result<reqwest::RequestBuilder> {
+    pub fn put<U: IntoUrl>(&self, url: U) -> AuthorizedRequestBuilder<A> {
         self.request(reqwest::Method::PUT, url)
     }
 
     /// Convenience method to make a `PATCH` request to a URL.
-    ///
-    /// # Errors
-    /// See [`request`](Self::request).
-    pub fn patch<U: IntoUrl>(&self, url: U) -> Result<reqwest::RequestBuilder> {
+    pub fn patch<U: IntoUrl>(&self, url: U) -> AuthorizedRequestBuilder<A> {
         self.request(reqwest::Method::PATCH, url)
     }
 
     /// Convenience method to make a `DELETE` request to a URL.
-    ///
-    /// # Errors
-    /// See [`request`](Self::request).
-    pub fn delete<U: IntoUrl>(&self, url: U) -> Result<reqwest::RequestBuilder> {
+    pub fn delete<U: IntoUrl>(&self, url: U) -> AuthorizedRequestBuilder<A> {
         self.request(reqwest::Method::DELETE, url)
     }
 
     /// Convenience method to make a `HEAD` request to a URL.
+    pub fn head<U: IntoUrl>(&self, url: U) -> AuthorizedRequestBuilder<A> {
+        self.request(reqwest::Method::HEAD, url)
+    }
+}
+
+/// Builder for an [`HttpClient`], mirroring `reqwest::ClientBuilder`.
+///
+/// Options not covered by a dedicated method, for example those of optional
+/// `reqwest` features, can be set with [`HttpClientBuilder::map`].
+#[must_use]
+#[derive(Debug)]
+pub struct HttpClientBuilder<A: Authorizer> {
+    authorizer: A,
+    builder: reqwest::ClientBuilder,
+    origin_policy: Option<OriginPolicy>,
+}
+
+impl<A: Authorizer> HttpClientBuilder<A> {
+    /// Creates a builder using the given `Authorizer`.
+    pub fn new(authorizer: A) -> Self {
+        Self {
+            authorizer,
+            builder: reqwest::Client::builder(),
+            origin_policy: None,
+        }
+    }
+
+    /// Set the `User-Agent` header sent with every request.
+    pub fn user_agent<V>(self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.map(|builder| builder.user_agent(value))
+    }
+
+    /// Set the default headers sent with every request.
+    pub fn default_headers(self, headers: HeaderMap) -> Self {
+        self.map(|builder| builder.default_headers(headers))
+    }
+
+    /// Set the redirect policy. Defaults to following up to 10 redirects.
+    pub fn redirect(self, policy: reqwest::redirect::Policy) -> Self {
+        self.map(|builder| builder.redirect(policy))
+    }
+
+    /// Enable or disable setting the `Referer` header on redirects.
+    pub fn referer(self, enable: bool) -> Self {
+        self.map(|builder| builder.referer(enable))
+    }
+
+    /// Add a proxy used for requests.
+    pub fn proxy(self, proxy: reqwest::Proxy) -> Self {
+        self.map(|builder| builder.proxy(proxy))
+    }
+
+    /// Disable all proxies, including the system proxy.
+    pub fn no_proxy(self) -> Self {
+        self.map(reqwest::ClientBuilder::no_proxy)
+    }
+
+    /// Set a total timeout for each request.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.map(|builder| builder.timeout(timeout))
+    }
+
+    /// Set a timeout for each read of a response.
+    pub fn read_timeout(self, timeout: Duration) -> Self {
+        self.map(|builder| builder.read_timeout(timeout))
+    }
+
+    /// Set a timeout for establishing connections.
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        self.map(|builder| builder.connect_timeout(timeout))
+    }
+
+    /// Set how long idle connections are kept in the pool. `None` disables the timeout.
+    pub fn pool_idle_timeout(self, timeout: Option<Duration>) -> Self {
+        self.map(|builder| builder.pool_idle_timeout(timeout))
+    }
+
+    /// Set the maximum number of idle connections per host.
+    pub fn pool_max_idle_per_host(self, max: usize) -> Self {
+        self.map(|builder| builder.pool_max_idle_per_host(max))
+    }
+
+    /// Enable or disable `TCP_NODELAY`.
+    pub fn tcp_nodelay(self, enabled: bool) -> Self {
+        self.map(|builder| builder.tcp_nodelay(enabled))
+    }
+
+    /// Set the interval of TCP keepalive probes. `None` disables them.
+    pub fn tcp_keepalive(self, interval: Option<Duration>) -> Self {
+        self.map(|builder| builder.tcp_keepalive(interval))
+    }
+
+    /// Bind connections to the given local address.
+    pub fn local_address(self, address: Option<IpAddr>) -> Self {
+        self.map(|builder| builder.local_address(address))
+    }
+
+    /// Only allow requests using `https`, so credentials are never sent in plain text.
+    pub fn https_only(self, enabled: bool) -> Self {
+        self.map(|builder| builder.https_only(enabled))
+    }
+
+    /// Only add credentials to requests to URLs allowed by `policy`, and do not follow
+    /// redirects to other origins, see [`OriginPolicy::same_origin_redirects`].
+    /// A redirect policy set afterwards with [`HttpClientBuilder::redirect`] takes precedence.
+    pub fn origin_policy(mut self, policy: OriginPolicy) -> Self {
+        self.origin_policy = Some(policy);
+        self.redirect(OriginPolicy::same_origin_redirects())
+    }
+
+    /// Modify the wrapped `reqwest::ClientBuilder`, for example to configure TLS.
+    pub fn map(mut self, f: impl FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder) -> Self {
+        self.builder = f(self.builder);
+        self
+    }
+
+    /// Build the `HttpClient`.
     ///
     /// # Errors
-    /// See [`request`](Self::request).
-    pub fn head<U: IntoUrl>(&self, url: U) -> Result<reqwest::RequestBuilder> {
-        self.request(reqwest::Method::HEAD, url)
+    /// Returns an error if the `reqwest::Client` cannot be built, for example because
+    /// the TLS backend fails to initialize.
+    pub fn build(self) -> Result<HttpClient<A>> {
+        let client = self.builder.build().map_err(Arc::new)?;
+        let client = HttpClient::from((client, self.authorizer));
+        Ok(match self.origin_policy {
+            Some(policy) => client.set_origin_policy(policy),
+            None => client,
+        })
     }
 }
 
+pub(crate) fn query_value(header: &HeaderValue) -> Result<&str> {
+    header.to_str().map_err(|_| Error::InvalidHeaderValue)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,7 +379,6 @@ mod tests {
 
         let response = client
             .get(format!("{}/get", server.url()))
-            .unwrap()
             .send()
             .await
             .unwrap();
@@ -157,4 +386,285 @@ mod tests {
         mock.assert_async().await;
         assert!(response.status().is_success());
     }
+
+    #[tokio::test]
+    async fn test_http_client_credential_locations() {
+        use crate::authorizers::ApiKeyAuthorizer;
+
+        let mut server = mockito::Server::new_async().await;
+        let header_mock = server
+            .mock("GET", "/header")
+            .match_header("x-api-key", "my-key")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+        let query_mock = server
+            .mock("GET", "/query")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("a".to_string(), "b".to_string()),
+                mockito::Matcher::UrlEncoded("api_key".to_string(), "my-key".to_string()),
+            ]))
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(ApiKeyAuthorizer::header("X-API-Key", "my-key").unwrap());
+        let url = format!("{}/header", server.url());
+        client.get(&url).send().await.unwrap();
+        let request = client.get(&url).build().unwrap();
+        client.execute(request).await.unwrap();
+        header_mock.assert_async().await;
+
+        let client = HttpClient::new(ApiKeyAuthorizer::query("api_key", "my-key").unwrap());
+        let url = format!("{}/query?a=b", server.url());
+        client.get(&url).send().await.unwrap();
+        let request = reqwest::Client::new().get(&url).build().unwrap();
+        client.execute(request).await.unwrap();
+        query_mock.assert_async().await;
+    }
+
+    /// Authorizer whose token can be replaced or removed by the test.
+    #[derive(Debug, Default)]
+    struct SwitchableAuthorizer(std::sync::Mutex<Option<&'static str>>);
+
+    impl Authorizer for SwitchableAuthorizer {
+        fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+            let token = self.0.lock().unwrap().ok_or(Error::TokenExpired)?;
+            Ok(Arc::new(
+                HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_builder_resolves_header_on_send() {
+        let mut server = mockito::Server::new_async().await;
+        let authorized = server
+            .mock("GET", "/get")
+            .match_header("authorization", "Bearer new")
+            .with_status(200)
+            .create_async()
+            .await;
+        let anonymous = server
+            .mock("GET", "/public")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(SwitchableAuthorizer::default());
+        // No token yet: errors are only surfaced when sending.
+        let request = client
+            .get(format!("{}/get", server.url()))
+            .header("x-custom", "value");
+        let retry = request.try_clone().unwrap();
+        assert!(matches!(request.send().await, Err(Error::TokenExpired)));
+
+        *client.authorizer.0.lock().unwrap() = Some("new");
+        // Builders own a clone of the client and can be moved to other tasks.
+        tokio::spawn(retry.send()).await.unwrap().unwrap();
+        authorized.assert_async().await;
+
+        *client.authorizer.0.lock().unwrap() = None;
+        client
+            .get(format!("{}/public", server.url()))
+            .without_authorization()
+            .send()
+            .await
+            .unwrap();
+        anonymous.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_http_client_builder() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/get")
+            .match_header("authorization", "Bearer test")
+            .match_header("user-agent", "middle-test")
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = HttpClient::builder(SwitchableAuthorizer(Some("test").into()))
+            .user_agent("middle-test")
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        // `SwitchableAuthorizer` is not `Clone`, but the client is.
+        let cloned = client.clone();
+        let url = format!("{}/get", server.url());
+        cloned.get(&url).send().await.unwrap();
+
+        let authorizer = BearerTokenAuthorizer::new("test").unwrap();
+        let client = HttpClient::from((client.client().clone(), authorizer));
+        client.get(&url).send().await.unwrap();
+        assert!(client.authorizer().authorization_header().is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_origin_policy() {
+        use crate::{OriginViolation, authorizers::ApiKeyAuthorizer};
+
+        let mut server = mockito::Server::new_async().await;
+        let foreign = mockito::Server::new_async().await;
+        let redirect = server
+            .mock("GET", "/redirect")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "api_key".to_string(),
+                "my-key".to_string(),
+            ))
+            .with_status(302)
+            .with_header("location", &format!("{}/leak", foreign.url()))
+            .create_async()
+            .await;
+        let stripped = server
+            .mock("GET", "/stripped")
+            .match_query(mockito::Matcher::Missing)
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let own: url::Url = server.url().parse().unwrap();
+        let client = HttpClient::builder(ApiKeyAuthorizer::query("api_key", "my-key").unwrap())
+            .origin_policy(OriginPolicy::new().allow_origin(&own))
+            .build()
+            .unwrap();
+        // The cross-origin redirect is returned instead of followed with the key.
+        let response = client
+            .get(format!("{}/redirect", server.url()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 302);
+        redirect.assert_async().await;
+        assert!(matches!(
+            client.get("https://example.com/").send().await,
+            Err(Error::OriginNotAllowed(origin)) if origin == "https://example.com"
+        ));
+
+        // Allow the test server by host only, which excludes plain HTTP.
+        let client = HttpClient::new(ApiKeyAuthorizer::query("api_key", "my-key").unwrap())
+            .set_origin_policy(
+                OriginPolicy::new()
+                    .allow_host("example.com")
+                    .on_violation(OriginViolation::StripCredentials),
+            );
+        client
+            .get(format!("{}/stripped", server.url()))
+            .send()
+            .await
+            .unwrap();
+        stripped.assert_async().await;
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_trace_context_propagation() {
+        use opentelemetry::trace::TracerProvider;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        // The subscriber is installed for the current thread only, so the test
+        // must run on a single-threaded runtime.
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        opentelemetry::global::set_text_map_propagator(
+            opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/get")
+            .match_header("authorization", "Bearer test")
+            .match_header(
+                "traceparent",
+                mockito::Matcher::Regex("^00-[0-9a-f]{32}-[0-9a-f]{16}-01$".to_string()),
+            )
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let authorizer = BearerTokenAuthorizer::new("test").unwrap();
+        let client = HttpClient::new(authorizer);
+        let url = format!("{}/get", server.url());
+
+        let span = tracing::info_span!("parent");
+        let _entered = span.enter();
+        let request = client.get(&url).build().unwrap();
+        client.execute(request).await.unwrap();
+        client.get(&url).send().await.unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_span_redacts_query_credentials() {
+        use crate::authorizers::ApiKeyAuthorizer;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        /// Collects the recorded `url.full` of all spans.
+        #[derive(Clone, Default)]
+        struct UrlRecorder(Arc<std::sync::Mutex<Vec<String>>>);
+
+        impl tracing::field::Visit for UrlRecorder {
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "url.full" {
+                    self.0.lock().unwrap().push(format!("{value:?}"));
+                }
+            }
+        }
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for UrlRecorder {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                _id: &tracing::span::Id,
+                _ctx: tracing_subscriber::layer::Context<'_, S>,
+            ) {
+                attrs.record(&mut self.clone());
+            }
+        }
+
+        let recorder = UrlRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/query")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "api_key".to_string(),
+                "my-key".to_string(),
+            ))
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(ApiKeyAuthorizer::query("api_key", "my-key").unwrap());
+        client
+            .get(format!("{}/query?a=b", server.url()))
+            .send()
+            .await
+            .unwrap();
+        mock.assert_async().await;
+
+        let urls = recorder.0.lock().unwrap();
+        assert_eq!(urls.len(), 1);
+        assert!(!urls[0].contains("my-key"), "{}", urls[0]);
+        assert!(
+            urls[0].ends_with("/query?a=b&api_key=REDACTED"),
+            "{}",
+            urls[0]
+        );
+    }
 }
@@ -0,0 +1,43 @@
+//! Source of the current time used to track token expiry.
+use std::{
+    fmt::Debug,
+    time::{Instant, SystemTime},
+};
+
+/// Monotonic clock used by authorizers to decide when tokens expire and when to
+/// refresh them.
+///
+/// Set a custom clock with
+/// [`ClientCredentialAuthorizerBuilder::clock`](crate::ClientCredentialAuthorizerBuilder::clock)
+/// to test expiry deterministically, for example with
+/// [`ManualClock`](crate::testing::ManualClock) (feature `testing`).
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+
+    /// Returns the current wall-clock time, used to interpret absolute expiry
+    /// timestamps such as the `exp` claim.
+    ///
+    /// The default implementation returns [`SystemTime::now`].
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// The default [`Clock`].
+///
+/// With the `runtime-tokio` feature, the time is read from tokio, so that it
+/// follows `tokio::time::pause()` and `tokio::time::advance()` within a runtime that has the
+/// `test-util` feature enabled. Otherwise, and outside of a paused runtime, it is
+/// equal to [`Instant::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        #[cfg(feature = "runtime-tokio")]
+        return tokio::time::Instant::now().into_std();
+        #[cfg(not(feature = "runtime-tokio"))]
+        Instant::now()
+    }
+}
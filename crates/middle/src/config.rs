@@ -0,0 +1,540 @@
+//! Authorizer configuration from configuration files or environment variables,
+//! enabled by the `config` feature.
+#[cfg(feature = "client-credentials")]
+use std::{collections::HashMap, time::Duration};
+
+use serde::Deserialize;
+
+#[cfg(feature = "client-credentials")]
+use crate::authorizers::{BasicClientCredentialAuthorizer, BasicClientCredentialAuthorizerBuilder};
+use crate::{
+    authorizers::{AnyAuthorizer, BearerTokenAuthorizer},
+    error::Error,
+};
+
+/// Selects and configures an authorizer.
+///
+/// Deserializes from a map with a `type` field of either `client-credentials`,
+/// `bearer-token` or `none`, and the fields of the respective config:
+///
+/// ```yaml
+/// type: client-credentials
+/// client_id: my-client
+/// client_secret: my-secret
+/// token_url: https://idp.example.com/oauth2/token
+/// scopes: [my-scope]
+/// refresh_tolerance: 1m
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+#[non_exhaustive]
+#[allow(clippy::large_enum_variant)]
+pub enum AuthorizerConfig {
+    /// Client credential flow, see [`ClientCredentialsConfig`].
+    #[cfg(feature = "client-credentials")]
+    ClientCredentials(ClientCredentialsConfig),
+    /// Static bearer token, see [`BearerTokenConfig`].
+    BearerToken(BearerTokenConfig),
+    /// No authorization, see [`AnyAuthorizer::None`].
+    None,
+}
+
+impl AuthorizerConfig {
+    /// Read the configuration from environment variables.
+    ///
+    /// `{prefix}_AUTH_TYPE` selects the authorizer (`client-credentials`,
+    /// `bearer-token` or `none`). The remaining variables are read by
+    /// [`ClientCredentialsConfig::from_env`] or [`BearerTokenConfig::from_env`].
+    ///
+    /// # Errors
+    /// Fails if a required variable is missing or a value is invalid.
+    pub fn from_env(prefix: &str) -> Result<Self, Error> {
+        Self::from_lookup(prefix, |name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(prefix: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let vars = Vars { prefix, lookup };
+        match vars.required("AUTH_TYPE")?.as_str() {
+            #[cfg(feature = "client-credentials")]
+            "client-credentials" => {
+                ClientCredentialsConfig::from_vars(&vars).map(Self::ClientCredentials)
+            }
+            "bearer-token" => BearerTokenConfig::from_vars(&vars).map(Self::BearerToken),
+            "none" => Ok(Self::None),
+            other => Err(vars.invalid("AUTH_TYPE", &format!("unknown authorizer `{other}`"))),
+        }
+    }
+
+    /// Build the configured authorizer.
+    /// For client credentials, this triggers the initial token fetch.
+    ///
+    /// # Errors
+    /// Fails if the configuration is invalid or the authorizer cannot be built.
+    #[cfg_attr(not(feature = "client-credentials"), allow(clippy::unused_async))]
+    pub async fn build(self) -> Result<AnyAuthorizer, Error> {
+        Ok(match self {
+            #[cfg(feature = "client-credentials")]
+            Self::ClientCredentials(config) => config.build().await?.into(),
+            Self::BearerToken(config) => config.build()?.into(),
+            Self::None => AnyAuthorizer::None,
+        })
+    }
+}
+
+/// Configuration of a [`BearerTokenAuthorizer`].
+#[derive(Clone, Deserialize, veil::Redact)]
+#[non_exhaustive]
+pub struct BearerTokenConfig {
+    /// The token, without the `Bearer` prefix.
+    #[redact]
+    pub token: String,
+}
+
+impl BearerTokenConfig {
+    /// Create a config for the given token.
+    #[must_use]
+    pub fn new(token: &str) -> Self {
+        Self {
+            token: token.to_string(),
+        }
+    }
+
+    /// Read the token from the environment variable `{prefix}_TOKEN`.
+    ///
+    /// # Errors
+    /// Fails if the variable is missing.
+    pub fn from_env(prefix: &str) -> Result<Self, Error> {
+        Self::from_vars(&Vars {
+            prefix,
+            lookup: |name: &str| std::env::var(name).ok(),
+        })
+    }
+
+    fn from_vars(vars: &Vars<'_, impl Fn(&str) -> Option<String>>) -> Result<Self, Error> {
+        Ok(Self {
+            token: vars.required("TOKEN")?,
+        })
+    }
+
+    /// Build the [`BearerTokenAuthorizer`].
+    ///
+    /// # Errors
+    /// Fails if the token is not valid ASCII.
+    pub fn build(self) -> Result<BearerTokenAuthorizer, Error> {
+        BearerTokenAuthorizer::new(&self.token)
+    }
+}
+
+/// Configuration of a [`BasicClientCredentialAuthorizer`], mapping onto
+/// [`BasicClientCredentialAuthorizerBuilder`].
+///
+/// Durations are given in a human readable format such as `30s` or `1m 30s`.
+/// Unset optional fields use the defaults of the builder. Deserialization fails if
+/// [`Self::validate`] fails.
+#[cfg(feature = "client-credentials")]
+#[derive(Clone, Deserialize, veil::Redact)]
+// `remote = "Self"` generates an inherent `deserialize`, wrapped by the
+// validating `Deserialize` implementation below.
+#[serde(remote = "Self", deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ClientCredentialsConfig {
+    /// Client id.
+    pub client_id: String,
+    /// Client secret.
+    #[redact]
+    pub client_secret: String,
+    /// Token endpoint of the Identity Provider. Must be a `http` or `https` url.
+    pub token_url: url::Url,
+    /// Scopes to request.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Extra parameters of the token request.
+    #[serde(default)]
+    pub extra_params: HashMap<String, String>,
+    /// Refresh tokens this long before they expire.
+    #[serde(default, with = "humantime_serde")]
+    pub refresh_tolerance: Option<Duration>,
+    /// Maximum number of retries of a token request.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Interval between retries of a token request.
+    #[serde(default, with = "humantime_serde")]
+    pub retry_interval: Option<Duration>,
+    /// Refresh tokens in the background before they expire. Defaults to `true`.
+    #[serde(default = "default_enable_refresh")]
+    pub enable_refresh: bool,
+    /// Read the expiry from JWT access tokens if the token response has no `expires_in`.
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub jwt_expiry_fallback: bool,
+    /// Treat tokens as expired after at most this duration.
+    #[serde(default, with = "humantime_serde")]
+    pub max_token_lifetime: Option<Duration>,
+}
+
+#[cfg(feature = "client-credentials")]
+fn default_enable_refresh() -> bool {
+    true
+}
+
+#[cfg(feature = "client-credentials")]
+impl<'de> Deserialize<'de> for ClientCredentialsConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let config = Self::deserialize(deserializer)?;
+        config.validate().map_err(serde::de::Error::custom)?;
+        Ok(config)
+    }
+}
+
+#[cfg(feature = "client-credentials")]
+impl ClientCredentialsConfig {
+    /// Create a config with the given credentials and defaults for everything else.
+    #[must_use]
+    pub fn new(client_id: &str, client_secret: &str, token_url: url::Url) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            token_url,
+            scopes: Vec::new(),
+            extra_params: HashMap::new(),
+            refresh_tolerance: None,
+            max_retries: None,
+            retry_interval: None,
+            enable_refresh: true,
+            jwt_expiry_fallback: false,
+            max_token_lifetime: None,
+        }
+    }
+
+    /// Read the configuration from environment variables:
+    ///
+    /// * `{prefix}_CLIENT_ID` (required)
+    /// * `{prefix}_CLIENT_SECRET` (required)
+    /// * `{prefix}_TOKEN_URL` (required)
+    /// * `{prefix}_SCOPES`: separated by spaces or commas
+    /// * `{prefix}_REFRESH_TOLERANCE`: duration such as `30s`
+    /// * `{prefix}_MAX_RETRIES`
+    /// * `{prefix}_RETRY_INTERVAL`: duration such as `10ms`
+    /// * `{prefix}_ENABLE_REFRESH`: `true` or `false`
+    /// * `{prefix}_JWT_EXPIRY_FALLBACK`: `true` or `false`
+    /// * `{prefix}_MAX_TOKEN_LIFETIME`: duration such as `1h`
+    ///
+    /// Extra params cannot be set from the environment.
+    ///
+    /// # Errors
+    /// Fails if a required variable is missing or a value is invalid.
+    pub fn from_env(prefix: &str) -> Result<Self, Error> {
+        Self::from_vars(&Vars {
+            prefix,
+            lookup: |name: &str| std::env::var(name).ok(),
+        })
+    }
+
+    fn from_vars(vars: &Vars<'_, impl Fn(&str) -> Option<String>>) -> Result<Self, Error> {
+        let token_url = vars.required("TOKEN_URL")?;
+        let token_url =
+            url::Url::parse(&token_url).map_err(|e| vars.invalid("TOKEN_URL", &e.to_string()))?;
+
+        let mut config = Self::new(
+            &vars.required("CLIENT_ID")?,
+            &vars.required("CLIENT_SECRET")?,
+            token_url,
+        );
+        if let Some(scopes) = vars.optional("SCOPES") {
+            config.scopes = scopes
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|scope| !scope.is_empty())
+                .map(ToString::to_string)
+                .collect();
+        }
+        config.refresh_tolerance = vars.parse("REFRESH_TOLERANCE", humantime::parse_duration)?;
+        config.max_retries = vars.parse("MAX_RETRIES", str::parse)?;
+        config.retry_interval = vars.parse("RETRY_INTERVAL", humantime::parse_duration)?;
+        if let Some(enable_refresh) = vars.parse("ENABLE_REFRESH", str::parse)? {
+            config.enable_refresh = enable_refresh;
+        }
+        if let Some(jwt_expiry_fallback) = vars.parse("JWT_EXPIRY_FALLBACK", str::parse)? {
+            config.jwt_expiry_fallback = jwt_expiry_fallback;
+        }
+        config.max_token_lifetime = vars.parse("MAX_TOKEN_LIFETIME", humantime::parse_duration)?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the configuration for values the builder would reject or misuse.
+    ///
+    /// # Errors
+    /// Fails if the client id is empty or the token url is not a `http` or `https` url.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.client_id.is_empty() {
+            return Err(Error::InvalidConfig(
+                "`client_id` must not be empty".to_string(),
+            ));
+        }
+        if !matches!(self.token_url.scheme(), "http" | "https") {
+            return Err(Error::InvalidConfig(format!(
+                "`token_url` must be a http or https url, got scheme `{}`",
+                self.token_url.scheme()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create a [`BasicClientCredentialAuthorizerBuilder`] from this configuration.
+    ///
+    /// # Errors
+    /// Fails if [`Self::validate`] fails.
+    pub fn builder(self) -> Result<BasicClientCredentialAuthorizerBuilder, Error> {
+        self.validate()?;
+
+        let mut builder = BasicClientCredentialAuthorizerBuilder::new(
+            &self.client_id,
+            &self.client_secret,
+            self.token_url,
+        )
+        .add_scopes(&self.scopes);
+        for (name, value) in &self.extra_params {
+            builder = builder.add_extra_param(name, value);
+        }
+        if let Some(tolerance) = self.refresh_tolerance {
+            builder = builder.refresh_tolerance(tolerance);
+        }
+        if let Some(max_retries) = self.max_retries {
+            builder = builder.set_max_retries(max_retries);
+        }
+        if let Some(retry_interval) = self.retry_interval {
+            builder = builder.set_retry_interval(retry_interval);
+        }
+        if !self.enable_refresh {
+            builder = builder.disable_refresh();
+        }
+        if self.jwt_expiry_fallback {
+            builder = builder.jwt_expiry_fallback();
+        }
+        if let Some(lifetime) = self.max_token_lifetime {
+            builder = builder.max_token_lifetime(lifetime);
+        }
+        Ok(builder)
+    }
+
+    /// Build the [`BasicClientCredentialAuthorizer`]. This triggers the initial token fetch.
+    ///
+    /// # Errors
+    /// Fails if [`Self::validate`] fails or the initial token fetch fails.
+    pub async fn build(self) -> Result<BasicClientCredentialAuthorizer, Error> {
+        self.builder()?.build().await
+    }
+}
+
+/// Environment variables sharing a common prefix.
+struct Vars<'a, F> {
+    prefix: &'a str,
+    lookup: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> Vars<'_, F> {
+    fn name(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}_{name}", self.prefix)
+        }
+    }
+
+    fn optional(&self, name: &str) -> Option<String> {
+        (self.lookup)(&self.name(name)).filter(|value| !value.is_empty())
+    }
+
+    fn required(&self, name: &str) -> Result<String, Error> {
+        self.optional(name).ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "Environment variable `{}` is not set",
+                self.name(name)
+            ))
+        })
+    }
+
+    #[cfg(feature = "client-credentials")]
+    fn parse<T, E: std::fmt::Display>(
+        &self,
+        name: &str,
+        parse: impl Fn(&str) -> Result<T, E>,
+    ) -> Result<Option<T>, Error> {
+        self.optional(name)
+            .map(|value| parse(&value).map_err(|e| self.invalid(name, &e.to_string())))
+            .transpose()
+    }
+
+    fn invalid(&self, name: &str, reason: &str) -> Error {
+        Error::InvalidConfig(format!(
+            "Invalid value of environment variable `{}`: {reason}",
+            self.name(name)
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "client-credentials"))]
+mod tests {
+    use super::*;
+    use crate::authorizers::Authorizer;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_deserialize_client_credentials() {
+        let config: AuthorizerConfig = serde_json::from_value(serde_json::json!({
+            "type": "client-credentials",
+            "client_id": "my-client",
+            "client_secret": "my-secret",
+            "token_url": "https://idp.example.com/token",
+            "scopes": ["a", "b"],
+            "refresh_tolerance": "1m",
+            "retry_interval": "50ms",
+            "max_token_lifetime": "1h"
+        }))
+        .unwrap();
+
+        let AuthorizerConfig::ClientCredentials(config) = config else {
+            panic!("Expected client credentials config");
+        };
+        assert_eq!(config.client_id, "my-client");
+        assert_eq!(config.scopes, vec!["a", "b"]);
+        assert_eq!(config.refresh_tolerance, Some(Duration::from_secs(60)));
+        assert_eq!(config.retry_interval, Some(Duration::from_millis(50)));
+        assert_eq!(config.max_retries, None);
+        assert!(config.enable_refresh);
+        assert!(!config.jwt_expiry_fallback);
+        assert_eq!(config.max_token_lifetime, Some(Duration::from_secs(3600)));
+
+        let debug = format!("{config:?}");
+        assert!(!debug.contains("my-secret"));
+        assert!(debug.contains("my-client"));
+    }
+
+    #[test]
+    fn test_deserialize_invalid_values() {
+        let result = serde_json::from_value::<AuthorizerConfig>(serde_json::json!({
+            "type": "client-credentials",
+            "client_id": "my-client",
+            "client_secret": "my-secret",
+            "token_url": "https://idp.example.com/token",
+            "refresh_tolerance": "soon"
+        }));
+        assert!(result.is_err());
+
+        let result = serde_json::from_value::<AuthorizerConfig>(serde_json::json!({
+            "type": "client-credentials",
+            "client_id": "my-client",
+            "client_secret": "my-secret",
+            "token_url": "not a url"
+        }));
+        assert!(result.is_err());
+
+        let result = serde_json::from_value::<AuthorizerConfig>(serde_json::json!({
+            "type": "client-credentials",
+            "client_id": "my-client",
+            "client_secret": "my-secret",
+            "token_url": "ftp://idp.example.com/token"
+        }));
+        let err = result.unwrap_err().to_string();
+        assert!(
+            err.contains("`token_url` must be a http or https url"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_from_env() {
+        let config = AuthorizerConfig::from_lookup(
+            "IDP",
+            lookup(&[
+                ("IDP_AUTH_TYPE", "client-credentials"),
+                ("IDP_CLIENT_ID", "my-client"),
+                ("IDP_CLIENT_SECRET", "my-secret"),
+                ("IDP_TOKEN_URL", "https://idp.example.com/token"),
+                ("IDP_SCOPES", "a, b c"),
+                ("IDP_MAX_RETRIES", "5"),
+                ("IDP_ENABLE_REFRESH", "false"),
+                ("IDP_JWT_EXPIRY_FALLBACK", "true"),
+            ]),
+        )
+        .unwrap();
+
+        let AuthorizerConfig::ClientCredentials(config) = config else {
+            panic!("Expected client credentials config");
+        };
+        assert_eq!(config.client_secret, "my-secret");
+        assert_eq!(config.scopes, vec!["a", "b", "c"]);
+        assert_eq!(config.max_retries, Some(5));
+        assert!(!config.enable_refresh);
+        assert!(config.jwt_expiry_fallback);
+
+        let config = AuthorizerConfig::from_lookup(
+            "",
+            lookup(&[("AUTH_TYPE", "bearer-token"), ("TOKEN", "tok")]),
+        )
+        .unwrap();
+        assert!(matches!(config, AuthorizerConfig::BearerToken(c) if c.token == "tok"));
+
+        let config = AuthorizerConfig::from_lookup("", lookup(&[("AUTH_TYPE", "none")])).unwrap();
+        assert!(matches!(config, AuthorizerConfig::None));
+    }
+
+    #[test]
+    fn test_from_env_errors() {
+        let err = AuthorizerConfig::from_lookup(
+            "IDP",
+            lookup(&[
+                ("IDP_AUTH_TYPE", "client-credentials"),
+                ("IDP_CLIENT_ID", "my-client"),
+                ("IDP_TOKEN_URL", "https://idp.example.com/token"),
+            ]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("IDP_CLIENT_SECRET"), "{err}");
+
+        let err = AuthorizerConfig::from_lookup(
+            "IDP",
+            lookup(&[
+                ("IDP_AUTH_TYPE", "client-credentials"),
+                ("IDP_CLIENT_ID", "my-client"),
+                ("IDP_CLIENT_SECRET", "my-secret"),
+                ("IDP_TOKEN_URL", "ftp://idp.example.com/token"),
+            ]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig(_)), "{err}");
+
+        let err = AuthorizerConfig::from_lookup(
+            "IDP",
+            lookup(&[
+                ("IDP_AUTH_TYPE", "client-credentials"),
+                ("IDP_CLIENT_ID", "my-client"),
+                ("IDP_CLIENT_SECRET", "my-secret"),
+                ("IDP_TOKEN_URL", "https://idp.example.com/token"),
+                ("IDP_REFRESH_TOLERANCE", "10 parsecs"),
+            ]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("IDP_REFRESH_TOLERANCE"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_build_bearer_token() {
+        let authorizer = AuthorizerConfig::BearerToken(BearerTokenConfig::new("tok"))
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(
+            authorizer.authorization_header().unwrap().to_str().unwrap(),
+            "Bearer tok"
+        );
+    }
+}
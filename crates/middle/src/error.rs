@@ -6,6 +6,8 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error("Token cannot be used as a header value. Must be ASCII.")]
     InvalidHeaderValue,
+    #[error("Invalid header name: `{0}`")]
+    InvalidHeaderName(String),
     #[error("Request to fetch token failed: {0}")]
     OAuth2RequestFailed(String),
     #[error("Failed to parse token response: {0}")]
@@ -14,4 +16,37 @@ pub enum Error {
     ReqwestFailed(#[from] Arc<reqwest::Error>),
     #[error("Token has expired and a refresh has not yet succeeded.")]
     TokenExpired,
+    #[error("Token store failed: {0}")]
+    TokenStore(String),
+    #[error("Failed to obtain client secret: {0}")]
+    CredentialSource(String),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("No authorization is configured.")]
+    NoAuthorization,
+    #[error("Token refresh task stopped unexpectedly: {0}")]
+    RefreshTaskFailed(String),
+    #[error("Credentials are not sent to `{0}`, which is not allowed by the origin policy.")]
+    OriginNotAllowed(String),
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+    #[error("Failed to fetch JWKS: {0}")]
+    Jwks(String),
+    #[error("Token introspection failed: {0}")]
+    Introspection(String),
+}
+
+impl<TE: oauth2::ErrorResponse>
+    From<oauth2::RequestTokenError<oauth2::HttpClientError<reqwest::Error>, TE>> for Error
+{
+    fn from(value: oauth2::RequestTokenError<oauth2::HttpClientError<reqwest::Error>, TE>) -> Self {
+        match value {
+            oauth2::RequestTokenError::Request(e) => Error::OAuth2RequestFailed(e.to_string()),
+            oauth2::RequestTokenError::Parse(e, _) => Error::OAuth2ParseError(e.to_string()),
+            oauth2::RequestTokenError::ServerResponse(e) => {
+                Error::OAuth2RequestFailed(e.to_string())
+            }
+            oauth2::RequestTokenError::Other(e) => Error::OAuth2RequestFailed(e.clone()),
+        }
+    }
 }
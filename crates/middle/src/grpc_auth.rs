@@ -0,0 +1,548 @@
+//! Async authorization layer for `tonic` channels.
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use http::HeaderValue;
+use http_body::{Body, Frame, SizeHint};
+
+use crate::{
+    Authorizer, CredentialLocation,
+    error::{Error, Result},
+    runtime::{Runtime, default_runtime, timeout},
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Default for [`GrpcAuthLayer::refresh_timeout`].
+const DEFAULT_REFRESH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default for [`GrpcAuthLayer::max_replay_size`].
+const DEFAULT_MAX_REPLAY_SIZE: usize = 64 * 1024;
+
+/// [`tower_layer::Layer`] adding the credentials of an [`Authorizer`] to gRPC requests,
+/// for use with `tonic` channels instead of the synchronous interceptors.
+///
+/// Compared to the interceptors, the layer
+/// * waits for a refresh, at most [`GrpcAuthLayer::refresh_timeout`], if the
+///   authorizer has no valid credentials, instead of failing immediately.
+/// * forces a refresh with [`Authorizer::refresh`] and retries once if the server
+///   responds with `UNAUTHENTICATED`, see [`GrpcAuthLayer::retry_unauthenticated`].
+///
+/// ```no_run
+/// # async fn example(authorizer: middle::BasicClientCredentialAuthorizer) {
+/// use tower_layer::Layer;
+///
+/// let channel = tonic::transport::Channel::from_static("http://[::1]:50051")
+///     .connect_lazy();
+/// let channel = middle::GrpcAuthLayer::new(authorizer).layer(channel);
+/// // let client = MyServiceClient::new(channel);
+/// # }
+/// ```
+///
+/// Credentials are not inserted if the request already has the header. Requests fail
+/// with an `unauthenticated` status if no credentials are available after waiting, and
+/// with an `internal` status if the authorizer places its credentials in a query parameter.
+pub struct GrpcAuthLayer<A> {
+    authorizer: Arc<A>,
+    config: Config,
+}
+
+#[derive(Debug, Clone)]
+struct Config {
+    refresh_timeout: Duration,
+    retry_unauthenticated: bool,
+    max_replay_size: usize,
+    runtime: Option<Arc<dyn Runtime>>,
+}
+
+impl<A: Authorizer> GrpcAuthLayer<A> {
+    /// Create a layer using the given `Authorizer`.
+    #[must_use]
+    pub fn new(authorizer: A) -> Self {
+        Self {
+            authorizer: Arc::new(authorizer),
+            config: Config {
+                refresh_timeout: DEFAULT_REFRESH_TIMEOUT,
+                retry_unauthenticated: true,
+                max_replay_size: DEFAULT_MAX_REPLAY_SIZE,
+                runtime: default_runtime(),
+            },
+        }
+    }
+
+    /// Set how long a request waits for a refresh if the authorizer has no valid
+    /// credentials. Defaults to 5 seconds.
+    #[must_use]
+    pub fn refresh_timeout(mut self, refresh_timeout: Duration) -> Self {
+        self.config.refresh_timeout = refresh_timeout;
+        self
+    }
+
+    /// Enable or disable retrying requests rejected with `UNAUTHENTICATED` once, after
+    /// forcing a refresh. Enabled by default.
+    ///
+    /// Only responses without a message body (trailers-only responses, as sent for
+    /// rejected credentials) are retried, and only if the server read the complete
+    /// request before responding and the request body is at most
+    /// [`GrpcAuthLayer::max_replay_size`] bytes. Streaming requests are never delayed.
+    #[must_use]
+    pub fn retry_unauthenticated(mut self, retry: bool) -> Self {
+        self.config.retry_unauthenticated = retry;
+        self
+    }
+
+    /// Set the maximum size of request bodies that are kept to retry the request.
+    /// Defaults to 64 KiB.
+    #[must_use]
+    pub fn max_replay_size(mut self, max_replay_size: usize) -> Self {
+        self.config.max_replay_size = max_replay_size;
+        self
+    }
+
+    /// Set the runtime used to bound the wait for refreshes.
+    /// Defaults to the runtime selected by the `runtime-*` features. Without a
+    /// runtime, requests wait for the refresh without a timeout.
+    #[must_use]
+    pub fn runtime(mut self, runtime: impl Runtime) -> Self {
+        self.config.runtime = Some(Arc::new(runtime));
+        self
+    }
+}
+
+impl<A> Clone for GrpcAuthLayer<A> {
+    fn clone(&self) -> Self {
+        Self {
+            authorizer: self.authorizer.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<A: Debug> Debug for GrpcAuthLayer<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcAuthLayer")
+            .field("authorizer", &self.authorizer)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S, A> tower_layer::Layer<S> for GrpcAuthLayer<A> {
+    type Service = GrpcAuthService<S, A>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcAuthService {
+            inner,
+            authorizer: self.authorizer.clone(),
+            config: Arc::new(self.config.clone()),
+        }
+    }
+}
+
+/// Service created by [`GrpcAuthLayer`].
+pub struct GrpcAuthService<S, A> {
+    inner: S,
+    authorizer: Arc<A>,
+    config: Arc<Config>,
+}
+
+impl<S: Clone, A> Clone for GrpcAuthService<S, A> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            authorizer: self.authorizer.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S: Debug, A: Debug> Debug for GrpcAuthService<S, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcAuthService")
+            .field("inner", &self.inner)
+            .field("authorizer", &self.authorizer)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S, A, B> tower_service::Service<http::Request<B>> for GrpcAuthService<S, A>
+where
+    S: tower_service::Service<
+            http::Request<tonic::body::Body>,
+            Response = http::Response<tonic::body::Body>,
+        > + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+    A: Authorizer + Send + Sync + 'static,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // Use the service that was polled ready, leave a fresh clone in its place.
+        let clone = self.inner.clone();
+        let inner = std::mem::replace(&mut self.inner, clone);
+        let authorizer = self.authorizer.clone();
+        let config = self.config.clone();
+        Box::pin(call(inner, authorizer, config, request))
+    }
+}
+
+async fn call<S, A, B>(
+    mut inner: S,
+    authorizer: Arc<A>,
+    config: Arc<Config>,
+    request: http::Request<B>,
+) -> std::result::Result<S::Response, S::Error>
+where
+    S: tower_service::Service<
+            http::Request<tonic::body::Body>,
+            Response = http::Response<tonic::body::Body>,
+        >,
+    A: Authorizer + Send + Sync + 'static,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    let CredentialLocation::Header(name) = authorizer.credential_location() else {
+        return Ok(tonic::Status::internal(
+            "Credentials in query parameters are not supported for gRPC requests",
+        )
+        .into_http());
+    };
+    let (mut parts, body) = request.into_parts();
+    // Credentials set by the caller are left untouched and not refreshed.
+    if parts.headers.contains_key(&name) {
+        return inner
+            .call(http::Request::from_parts(
+                parts,
+                tonic::body::Body::new(body),
+            ))
+            .await;
+    }
+
+    match wait_for_header(&*authorizer, &config).await {
+        Ok(Some(header)) => {
+            parts
+                .headers
+                .insert(name.clone(), Arc::unwrap_or_clone(header));
+        }
+        Ok(None) => {}
+        Err(e) => return Ok(tonic::Status::unauthenticated(e.to_string()).into_http()),
+    }
+
+    if !config.retry_unauthenticated {
+        return inner
+            .call(http::Request::from_parts(
+                parts,
+                tonic::body::Body::new(body),
+            ))
+            .await;
+    }
+
+    let sent = parts.headers.get(&name).cloned();
+    let recording = Arc::new(Mutex::new(Recording::Partial(BytesMut::new())));
+    let body = RecordingBody {
+        body: Box::pin(body),
+        recording: recording.clone(),
+        max_size: config.max_replay_size,
+    };
+    let response = inner
+        .call(http::Request::from_parts(
+            parts.clone(),
+            tonic::body::Body::new(body),
+        ))
+        .await?;
+    if !is_unauthenticated(&response) {
+        return Ok(response);
+    }
+    let Some(replay) = recording.lock().expect("Non-poisoned lock").take_complete() else {
+        return Ok(response);
+    };
+
+    if let Err(e) = bounded(&config, authorizer.refresh()).await {
+        tracing::warn!("Refresh after UNAUTHENTICATED response failed: {e}");
+        return Ok(response);
+    }
+    // Resending identical credentials, for example of static authorizers, would be
+    // rejected again.
+    match authorizer.optional_authorization_header() {
+        Ok(Some(header)) if sent.as_ref() != Some(&*header) => {
+            parts.headers.insert(name, Arc::unwrap_or_clone(header))
+        }
+        Ok(_) | Err(_) => return Ok(response),
+    };
+    std::future::poll_fn(|cx| inner.poll_ready(cx)).await?;
+    inner
+        .call(http::Request::from_parts(
+            parts,
+            tonic::body::Body::new(http_body_util::Full::new(replay)),
+        ))
+        .await
+}
+
+/// Returns the credentials of `authorizer`, waiting for a refresh if none are available.
+async fn wait_for_header<A: Authorizer + ?Sized>(
+    authorizer: &A,
+    config: &Config,
+) -> Result<Option<Arc<HeaderValue>>> {
+    if let Ok(header) = authorizer.optional_authorization_header() {
+        return Ok(header);
+    }
+    // A failed refresh does not discard credentials that are still valid, so check
+    // the authorizer again regardless of the outcome.
+    if let Err(e) = bounded(config, authorizer.refresh()).await {
+        tracing::debug!("Refresh for gRPC request failed: {e}");
+    }
+    authorizer.optional_authorization_header()
+}
+
+/// Wait for `refresh`, at most [`Config::refresh_timeout`].
+async fn bounded(config: &Config, refresh: impl Future<Output = Result<()>>) -> Result<()> {
+    match &config.runtime {
+        Some(runtime) => timeout(&**runtime, config.refresh_timeout, refresh)
+            .await
+            .unwrap_or(Err(Error::TokenExpired)),
+        None => refresh.await,
+    }
+}
+
+/// Returns `true` for trailers-only responses with status `UNAUTHENTICATED`, and
+/// for HTTP 401 responses.
+fn is_unauthenticated(response: &http::Response<tonic::body::Body>) -> bool {
+    response.status() == http::StatusCode::UNAUTHORIZED
+        || tonic::Status::from_header_map(response.headers())
+            .is_some_and(|status| status.code() == tonic::Code::Unauthenticated)
+}
+
+/// Copy of a request body kept while it is sent, to retry the request.
+#[derive(Debug)]
+enum Recording {
+    Partial(BytesMut),
+    Complete(Bytes),
+    Discarded,
+}
+
+impl Recording {
+    /// Returns the body if it was sent completely.
+    fn take_complete(&mut self) -> Option<Bytes> {
+        match std::mem::replace(self, Self::Discarded) {
+            Self::Complete(body) => Some(body),
+            Self::Partial(_) | Self::Discarded => None,
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Self::Partial(body) = self {
+            *self = Self::Complete(std::mem::take(body).freeze());
+        }
+    }
+}
+
+/// Body forwarding `body` while copying it into `recording`.
+struct RecordingBody<B> {
+    body: Pin<Box<B>>,
+    recording: Arc<Mutex<Recording>>,
+    max_size: usize,
+}
+
+impl<B: Body<Data = Bytes>> Body for RecordingBody<B> {
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::result::Result<Frame<Bytes>, Self::Error>>> {
+        let frame = std::task::ready!(self.body.as_mut().poll_frame(cx));
+        let mut recording = self.recording.lock().expect("Non-poisoned lock");
+        match &frame {
+            Some(Ok(frame)) => match (&mut *recording, frame.data_ref()) {
+                (Recording::Partial(body), Some(data))
+                    if body.len() + data.len() <= self.max_size =>
+                {
+                    body.extend_from_slice(data);
+                }
+                // gRPC requests have no trailers, so don't attempt to replay them.
+                _ => *recording = Recording::Discarded,
+            },
+            Some(Err(_)) => *recording = Recording::Discarded,
+            None => recording.finish(),
+        }
+        drop(recording);
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        let end = self.body.is_end_stream();
+        if end {
+            self.recording.lock().expect("Non-poisoned lock").finish();
+        }
+        end
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(all(test, feature = "runtime-tokio"))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use http_body_util::BodyExt;
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    use super::*;
+
+    /// Authorizer without credentials until the first refresh, returning
+    /// `Bearer v<n>` after the n-th refresh.
+    #[derive(Debug, Default)]
+    struct VersionedAuthorizer {
+        version: AtomicUsize,
+        hang: bool,
+    }
+
+    impl Authorizer for VersionedAuthorizer {
+        fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+            match self.version.load(Ordering::SeqCst) {
+                0 => Err(Error::TokenExpired),
+                version => Ok(Arc::new(
+                    HeaderValue::from_str(&format!("Bearer v{version}")).unwrap(),
+                )),
+            }
+        }
+
+        fn refresh(&self) -> crate::RefreshFuture<'_> {
+            if self.hang {
+                return Box::pin(std::future::pending());
+            }
+            self.version.fetch_add(1, Ordering::SeqCst);
+            Box::pin(std::future::ready(Ok(())))
+        }
+    }
+
+    /// Authorization header and body of each request received by [`Server`].
+    type Requests = Vec<(Option<HeaderValue>, Bytes)>;
+
+    /// Service rejecting all credentials but `Bearer v2`, recording the requests.
+    #[derive(Debug, Clone, Default)]
+    struct Server {
+        requests: Arc<Mutex<Requests>>,
+    }
+
+    impl Service<http::Request<tonic::body::Body>> for Server {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future =
+            Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<tonic::body::Body>) -> Self::Future {
+            let requests = self.requests.clone();
+            Box::pin(async move {
+                let header = request.headers().get(http::header::AUTHORIZATION).cloned();
+                let body = request.into_body().collect().await.unwrap().to_bytes();
+                let status = match &header {
+                    Some(header) if header == "Bearer v2" => tonic::Status::ok(""),
+                    _ => tonic::Status::unauthenticated("invalid token"),
+                };
+                requests.lock().unwrap().push((header, body));
+                Ok(status.into_http())
+            })
+        }
+    }
+
+    fn status(response: &http::Response<tonic::body::Body>) -> tonic::Code {
+        tonic::Status::from_header_map(response.headers())
+            .unwrap()
+            .code()
+    }
+
+    #[tokio::test]
+    async fn test_grpc_auth_layer_waits_and_retries() {
+        let server = Server::default();
+        let mut service = GrpcAuthLayer::new(VersionedAuthorizer::default()).layer(server.clone());
+
+        let body = http_body_util::Full::new(Bytes::from_static(b"message"));
+        let response = service.call(http::Request::new(body)).await.unwrap();
+        assert_eq!(status(&response), tonic::Code::Ok);
+        // Waited for the first refresh, then retried after the second.
+        assert_eq!(
+            *server.requests.lock().unwrap(),
+            vec![
+                (
+                    Some(HeaderValue::from_static("Bearer v1")),
+                    Bytes::from_static(b"message")
+                ),
+                (
+                    Some(HeaderValue::from_static("Bearer v2")),
+                    Bytes::from_static(b"message")
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_grpc_auth_layer_does_not_resend_unchanged_credentials() {
+        let server = Server::default();
+        let authorizer = crate::BearerTokenAuthorizer::new("v1").unwrap();
+        let mut service = GrpcAuthLayer::new(authorizer).layer(server.clone());
+
+        let body = http_body_util::Full::new(Bytes::from_static(b"message"));
+        let response = service.call(http::Request::new(body)).await.unwrap();
+        assert_eq!(status(&response), tonic::Code::Unauthenticated);
+        assert_eq!(server.requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_auth_layer_limits() {
+        // Bodies above the replay limit are not retried.
+        let server = Server::default();
+        let mut service = GrpcAuthLayer::new(VersionedAuthorizer::default())
+            .max_replay_size(4)
+            .layer(server.clone());
+        let body = http_body_util::Full::new(Bytes::from_static(b"message"));
+        let response = service.call(http::Request::new(body)).await.unwrap();
+        assert_eq!(status(&response), tonic::Code::Unauthenticated);
+        assert_eq!(server.requests.lock().unwrap().len(), 1);
+
+        // The wait for a refresh is bounded.
+        let server = Server::default();
+        let authorizer = VersionedAuthorizer {
+            hang: true,
+            ..Default::default()
+        };
+        let mut service = GrpcAuthLayer::new(authorizer)
+            .refresh_timeout(Duration::from_millis(10))
+            .layer(server.clone());
+        let response = service
+            .call(http::Request::new(tonic::body::Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(status(&response), tonic::Code::Unauthenticated);
+        assert!(server.requests.lock().unwrap().is_empty());
+    }
+}
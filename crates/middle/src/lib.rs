@@ -7,8 +7,42 @@
 #![forbid(unsafe_code)]
 
 mod authorizers;
+#[cfg(feature = "blocking")]
+mod blocking_client;
 mod client;
+mod clock;
+#[cfg(feature = "config")]
+mod config;
 pub mod error;
+#[cfg(all(feature = "tonic", feature = "client-credentials"))]
+mod grpc_auth;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod origin;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod request_builder;
+#[cfg(feature = "client-credentials")]
+mod runtime;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub use authorizers::*;
+#[cfg(feature = "blocking")]
+pub use blocking_client::*;
 pub use client::*;
+pub use clock::{Clock, SystemClock};
+#[cfg(feature = "config")]
+pub use config::*;
 pub use error::{Error, Result};
+#[cfg(all(feature = "tonic", feature = "client-credentials"))]
+pub use grpc_auth::{GrpcAuthLayer, GrpcAuthService};
+pub use origin::{OriginPolicy, OriginViolation};
+pub use request_builder::AuthorizedRequestBuilder;
+#[cfg(all(feature = "client-credentials", feature = "runtime-smol"))]
+pub use runtime::SmolRuntime;
+#[cfg(all(feature = "client-credentials", feature = "runtime-tokio"))]
+pub use runtime::TokioRuntime;
+#[cfg(feature = "client-credentials")]
+pub use runtime::{Runtime, RuntimeFuture, TaskHandle};
@@ -0,0 +1,113 @@
+//! Metrics recorded via the [`metrics`] facade if the `metrics` feature is enabled.
+//!
+//! Install a recorder (for example `metrics-exporter-prometheus`) to collect them.
+//! All metrics carry the labels `client_id` and `token_url`.
+use std::sync::Once;
+
+use metrics::{Counter, Gauge, Histogram, Unit};
+
+/// Counter of token requests sent to the Identity Provider, including retries.
+/// Labelled with `outcome` (`success` or `failure`).
+pub const TOKEN_REQUESTS_TOTAL: &str = "middle_token_requests_total";
+/// Counter of token requests that were retried after a failure.
+pub const TOKEN_REQUEST_RETRIES_TOTAL: &str = "middle_token_request_retries_total";
+/// Histogram of the latency of single token requests, in seconds.
+pub const TOKEN_REQUEST_DURATION_SECONDS: &str = "middle_token_request_duration_seconds";
+/// Gauge of the lifetime of the most recently fetched token, in seconds.
+/// Not updated for tokens without expiry.
+pub const TOKEN_EXPIRES_IN_SECONDS: &str = "middle_token_expires_in_seconds";
+/// Counter of authorization headers requested from the authorizer.
+/// Labelled with `outcome` (`served` or `error`).
+pub const AUTHORIZATION_HEADERS_TOTAL: &str = "middle_authorization_headers_total";
+
+static DESCRIBE: Once = Once::new();
+
+fn describe() {
+    DESCRIBE.call_once(|| {
+        metrics::describe_counter!(
+            TOKEN_REQUESTS_TOTAL,
+            "Token requests sent to the Identity Provider, including retries."
+        );
+        metrics::describe_counter!(
+            TOKEN_REQUEST_RETRIES_TOTAL,
+            "Token requests that were retried after a failure."
+        );
+        metrics::describe_histogram!(
+            TOKEN_REQUEST_DURATION_SECONDS,
+            Unit::Seconds,
+            "Latency of single token requests."
+        );
+        metrics::describe_gauge!(
+            TOKEN_EXPIRES_IN_SECONDS,
+            Unit::Seconds,
+            "Lifetime of the most recently fetched token."
+        );
+        metrics::describe_counter!(
+            AUTHORIZATION_HEADERS_TOTAL,
+            "Authorization headers requested from the authorizer."
+        );
+    });
+}
+
+/// Metric handles of a single client, registered once so that recording on the
+/// request hot path does not allocate labels.
+#[derive(Debug, Clone)]
+pub(crate) struct TokenMetrics {
+    requests_success: Counter,
+    requests_failure: Counter,
+    retries: Counter,
+    request_duration: Histogram,
+    expires_in: Gauge,
+    headers_served: Counter,
+    headers_error: Counter,
+}
+
+impl TokenMetrics {
+    pub(crate) fn new(client_id: &str, token_url: &str) -> Self {
+        describe();
+        let labels = [
+            ("client_id", client_id.to_string()),
+            ("token_url", token_url.to_string()),
+        ];
+        let with_outcome = |outcome: &'static str| {
+            let mut labels = labels.to_vec();
+            labels.push(("outcome", outcome.to_string()));
+            labels
+        };
+
+        Self {
+            requests_success: metrics::counter!(TOKEN_REQUESTS_TOTAL, &with_outcome("success")),
+            requests_failure: metrics::counter!(TOKEN_REQUESTS_TOTAL, &with_outcome("failure")),
+            retries: metrics::counter!(TOKEN_REQUEST_RETRIES_TOTAL, &labels),
+            request_duration: metrics::histogram!(TOKEN_REQUEST_DURATION_SECONDS, &labels),
+            expires_in: metrics::gauge!(TOKEN_EXPIRES_IN_SECONDS, &labels),
+            headers_served: metrics::counter!(AUTHORIZATION_HEADERS_TOTAL, &with_outcome("served")),
+            headers_error: metrics::counter!(AUTHORIZATION_HEADERS_TOTAL, &with_outcome("error")),
+        }
+    }
+
+    pub(crate) fn record_request(&self, duration: std::time::Duration, success: bool) {
+        self.request_duration.record(duration.as_secs_f64());
+        if success {
+            self.requests_success.increment(1);
+        } else {
+            self.requests_failure.increment(1);
+        }
+    }
+
+    pub(crate) fn record_retry(&self) {
+        self.retries.increment(1);
+    }
+
+    pub(crate) fn record_expires_in(&self, expires_in: std::time::Duration) {
+        self.expires_in.set(expires_in.as_secs_f64());
+    }
+
+    pub(crate) fn record_header<T, E>(&self, result: &Result<T, E>) {
+        if result.is_ok() {
+            self.headers_served.increment(1);
+        } else {
+            self.headers_error.increment(1);
+        }
+    }
+}
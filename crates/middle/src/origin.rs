@@ -0,0 +1,199 @@
+//! Restricting the URLs credentials are sent to.
+use url::{Host, Url};
+
+use crate::error::{Error, Result};
+
+/// Maximum number of redirects followed by [`OriginPolicy::same_origin_redirects`],
+/// equal to the default of `reqwest`.
+const MAX_REDIRECTS: usize = 10;
+
+/// What to do with a request to a URL that is not allowed by an [`OriginPolicy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OriginViolation {
+    /// Fail the request with [`Error::OriginNotAllowed`] without sending it.
+    #[default]
+    Refuse,
+    /// Send the request without the credentials of the authorizer.
+    StripCredentials,
+}
+
+/// Policy deciding to which URLs an [`HttpClient`](crate::HttpClient) sends the
+/// credentials of its authorizer.
+///
+/// By default, credentials are sent to any host over `https`, and over plain `http`
+/// only to `localhost` and loopback addresses. Once hosts or origins are added, only
+/// URLs matching one of them are allowed:
+///
+/// ```
+/// use middle::{OriginPolicy, OriginViolation};
+///
+/// let policy = OriginPolicy::new()
+///     .allow_host("api.example.com")
+///     .allow_host("*.internal.example.com")
+///     .on_violation(OriginViolation::StripCredentials);
+/// assert!(policy.is_allowed(&"https://api.example.com/data".parse().unwrap()));
+/// assert!(!policy.is_allowed(&"http://api.example.com/data".parse().unwrap()));
+/// assert!(!policy.is_allowed(&"https://example.com/data".parse().unwrap()));
+/// ```
+///
+/// The policy only applies to the credentials added by the authorizer, not to
+/// headers set explicitly on a request.
+#[derive(Debug, Clone, Default)]
+pub struct OriginPolicy {
+    hosts: Vec<String>,
+    origins: Vec<url::Origin>,
+    allow_plain_http: bool,
+    violation: OriginViolation,
+}
+
+impl OriginPolicy {
+    /// Create a policy allowing any host over `https`, see [`OriginPolicy`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `host` on any port. A leading `*.` matches all subdomains, but not the
+    /// domain itself.
+    #[must_use]
+    pub fn allow_host(mut self, host: impl Into<String>) -> Self {
+        self.hosts.push(host.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Allow exactly the scheme, host and port of `url`, including plain `http`.
+    #[must_use]
+    pub fn allow_origin(mut self, url: &Url) -> Self {
+        self.origins.push(url.origin());
+        self
+    }
+
+    /// Allow plain `http` to allowed hosts other than `localhost`. Defaults to `false`.
+    #[must_use]
+    pub fn allow_plain_http(mut self, allow: bool) -> Self {
+        self.allow_plain_http = allow;
+        self
+    }
+
+    /// Set what happens to requests to URLs that are not allowed.
+    /// Defaults to [`OriginViolation::Refuse`].
+    #[must_use]
+    pub fn on_violation(mut self, violation: OriginViolation) -> Self {
+        self.violation = violation;
+        self
+    }
+
+    /// Returns whether credentials may be sent to `url`.
+    #[must_use]
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        if self.origins.contains(&url.origin()) {
+            return true;
+        }
+        let scheme_allowed = match url.scheme() {
+            "https" => true,
+            "http" => self.allow_plain_http || is_localhost(url),
+            _ => false,
+        };
+        if !scheme_allowed {
+            return false;
+        }
+        if self.hosts.is_empty() && self.origins.is_empty() {
+            return true;
+        }
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        self.hosts
+            .iter()
+            .any(|allowed| match allowed.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.')),
+                None => host == allowed,
+            })
+    }
+
+    /// Returns whether credentials should be added to a request to `url`.
+    pub(crate) fn check(&self, url: &Url) -> Result<bool> {
+        if self.is_allowed(url) {
+            return Ok(true);
+        }
+        match self.violation {
+            OriginViolation::Refuse => {
+                Err(Error::OriginNotAllowed(url.origin().ascii_serialization()))
+            }
+            OriginViolation::StripCredentials => Ok(false),
+        }
+    }
+
+    /// Redirect policy that follows up to 10 redirects within the origin of the
+    /// original request, and returns the redirect response instead of following it
+    /// when it points to a different origin.
+    ///
+    /// `reqwest` removes the `Authorization` header on cross-origin redirects, but
+    /// keeps custom headers and query parameters, such as those of
+    /// [`CredentialLocation::Query`](crate::CredentialLocation::Query).
+    #[must_use]
+    pub fn same_origin_redirects() -> reqwest::redirect::Policy {
+        reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if attempt
+                .previous()
+                .first()
+                .is_some_and(|first| first.origin() != attempt.url().origin())
+            {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        })
+    }
+}
+
+fn is_localhost(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(policy: &OriginPolicy, url: &str) -> bool {
+        policy.is_allowed(&url.parse().unwrap())
+    }
+
+    #[test]
+    fn test_origin_policy() {
+        let policy = OriginPolicy::new();
+        assert!(allowed(&policy, "https://example.com/path"));
+        assert!(allowed(&policy, "http://localhost:8080/path"));
+        assert!(allowed(&policy, "http://127.0.0.1/path"));
+        assert!(allowed(&policy, "http://[::1]/path"));
+        assert!(!allowed(&policy, "http://example.com/path"));
+        assert!(!allowed(&policy, "ftp://example.com/path"));
+
+        let policy = OriginPolicy::new()
+            .allow_host("API.example.com")
+            .allow_host("*.internal.example.com")
+            .allow_origin(&"http://plain.example.com:8080".parse().unwrap());
+        assert!(allowed(&policy, "https://api.example.com/path"));
+        assert!(allowed(&policy, "https://api.example.com:8443/path"));
+        assert!(allowed(&policy, "https://a.b.internal.example.com/path"));
+        assert!(allowed(&policy, "http://plain.example.com:8080/path"));
+        assert!(!allowed(&policy, "http://api.example.com/path"));
+        assert!(!allowed(&policy, "https://internal.example.com/path"));
+        assert!(!allowed(&policy, "https://evilinternal.example.com/path"));
+        assert!(!allowed(&policy, "https://plain.example.com/path"));
+        assert!(!allowed(&policy, "http://localhost/path"));
+        assert!(allowed(
+            &policy.allow_plain_http(true),
+            "http://api.example.com/path"
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use std::{fmt::Display, time::Duration};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::{Authorizer, HttpClient, error::Result};
+
+/// Builder for a request sent by an [`HttpClient`], mirroring `reqwest::RequestBuilder`.
+///
+/// The credentials are resolved from the [`Authorizer`] when the request is sent
+/// (or built), not when the builder is created, so long-lived builders and
+/// retries always use the current token. Errors of the authorizer are returned
+/// by [`AuthorizedRequestBuilder::send`]. The builder holds a clone of the client,
+/// so it can be stored or moved into other tasks.
+///
+/// Credentials are not added if the request already carries them, for example
+/// because [`AuthorizedRequestBuilder::bearer_auth`] was used. To send a single
+/// request without credentials, use [`AuthorizedRequestBuilder::without_authorization`].
+#[must_use = "the request is not sent unless `send` is awaited"]
+pub struct AuthorizedRequestBuilder<A: Authorizer> {
+    client: HttpClient<A>,
+    builder: reqwest::RequestBuilder,
+    authorize: bool,
+}
+
+impl<A: Authorizer> AuthorizedRequestBuilder<A> {
+    pub(crate) fn new(client: HttpClient<A>, builder: reqwest::RequestBuilder) -> Self {
+        Self {
+            client,
+            builder,
+            authorize: true,
+        }
+    }
+
+    /// Send this request without credentials.
+    pub fn without_authorization(mut self) -> Self {
+        self.authorize = false;
+        self
+    }
+
+    /// Add a header to this request.
+    pub fn header<K, V>(self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.map(|builder| builder.header(key, value))
+    }
+
+    /// Merge the given headers into the headers of this request.
+    pub fn headers(self, headers: HeaderMap) -> Self {
+        self.map(|builder| builder.headers(headers))
+    }
+
+    /// Use HTTP Basic authentication instead of the credentials of the authorizer.
+    pub fn basic_auth<U: Display, P: Display>(self, username: U, password: Option<P>) -> Self {
+        self.map(|builder| builder.basic_auth(username, password))
+    }
+
+    /// Use the given bearer token instead of the credentials of the authorizer.
+    pub fn bearer_auth<T: Display>(self, token: T) -> Self {
+        self.map(|builder| builder.bearer_auth(token))
+    }
+
+    /// Set the body of this request.
+    pub fn body<T: Into<reqwest::Body>>(self, body: T) -> Self {
+        self.map(|builder| builder.body(body))
+    }
+
+    /// Set a timeout for this request, overriding the timeout of the client.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.map(|builder| builder.timeout(timeout))
+    }
+
+    /// Append the given parameters to the query string of the URL.
+    pub fn query<T: serde::Serialize + ?Sized>(self, query: &T) -> Self {
+        self.map(|builder| builder.query(query))
+    }
+
+    /// Send a form body, setting the `Content-Type` header accordingly.
+    pub fn form<T: serde::Serialize + ?Sized>(self, form: &T) -> Self {
+        self.map(|builder| builder.form(form))
+    }
+
+    /// Set the HTTP version of this request.
+    pub fn version(self, version: http::Version) -> Self {
+        self.map(|builder| builder.version(version))
+    }
+
+    /// Modify the wrapped `reqwest::RequestBuilder`, for example to use methods
+    /// of optional `reqwest` features such as `json`.
+    pub fn map(
+        mut self,
+        f: impl FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    ) -> Self {
+        self.builder = f(self.builder);
+        self
+    }
+
+    /// Attempt to clone this builder. Returns `None` if the body cannot be cloned.
+    #[must_use]
+    pub fn try_clone(&self) -> Option<Self> {
+        Some(Self {
+            client: self.client.clone(),
+            builder: self.builder.try_clone()?,
+            authorize: self.authorize,
+        })
+    }
+
+    /// Build the `Request`, including the current credentials.
+    ///
+    /// # Errors
+    /// - Returns an error if the authorizer fails to provide a token, typically because the token refresh failed.
+    /// - Returns an error if the request is invalid, for example because of an invalid URL.
+    pub fn build(self) -> Result<reqwest::Request> {
+        let mut request = self.builder.build().map_err(std::sync::Arc::new)?;
+        if self.authorize {
+            self.client.authorize(&mut request)?;
+        }
+        Ok(request)
+    }
+
+    /// Send the request, including the current credentials.
+    ///
+    /// # Errors
+    /// - Returns an error if the authorizer fails to provide a token, typically because the token refresh failed.
+    /// - Returns an error if the request is invalid or fails.
+    pub async fn send(self) -> Result<reqwest::Response> {
+        let client = self.client.clone();
+        let request = self.build()?;
+        client.send(request).await
+    }
+}
+
+impl<A: Authorizer> std::fmt::Debug for AuthorizedRequestBuilder<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthorizedRequestBuilder")
+            .field("builder", &self.builder)
+            .field("authorize", &self.authorize)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,270 @@
+//! Async runtime abstraction used to run background token refreshes.
+//!
+//! [`TokioRuntime`] (feature `runtime-tokio`) and [`SmolRuntime`] (feature
+//! `runtime-smol`) are provided. Other executors can be used by implementing
+//! [`Runtime`] and passing it to
+//! [`ClientCredentialAuthorizerBuilder::runtime`](crate::ClientCredentialAuthorizerBuilder::runtime).
+//!
+//! Note that token requests are sent with `reqwest`, which requires a `tokio`
+//! reactor for its I/O. Outside of a tokio runtime, a reactor must be provided,
+//! for example by entering a tokio runtime with
+//! [`Handle::enter`](https://docs.rs/tokio/latest/tokio/runtime/struct.Handle.html#method.enter)
+//! or by using the `async-compat` crate. [`SmolRuntime`] runs the refresh task
+//! within the tokio context that is current when the authorizer is built.
+use std::{fmt::Debug, future::Future, pin::Pin, task::Poll, time::Duration};
+
+/// Boxed future executed or returned by a [`Runtime`].
+pub type RuntimeFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Async runtime that runs the background refresh of authorizers.
+pub trait Runtime: Debug + Send + Sync + 'static {
+    /// Spawn `future` to run in the background.
+    fn spawn(&self, future: RuntimeFuture<'static>) -> Box<dyn TaskHandle>;
+
+    /// Returns a future that completes after `duration`.
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static>;
+}
+
+/// Handle to a task spawned by a [`Runtime`].
+pub trait TaskHandle: std::any::Any + Debug + Send + Sync {
+    /// Stop the task. Must be idempotent.
+    fn abort(&self);
+
+    /// Returns `true` if the task has completed or was aborted.
+    fn is_finished(&self) -> bool;
+}
+
+/// The default runtime if no other runtime is set on the builder.
+#[cfg_attr(
+    any(feature = "runtime-tokio", feature = "runtime-smol"),
+    allow(clippy::unnecessary_wraps)
+)]
+pub(crate) fn default_runtime() -> Option<std::sync::Arc<dyn Runtime>> {
+    #[cfg(feature = "runtime-tokio")]
+    return Some(std::sync::Arc::new(TokioRuntime));
+    #[cfg(all(feature = "runtime-smol", not(feature = "runtime-tokio")))]
+    return Some(std::sync::Arc::new(SmolRuntime));
+    #[cfg(not(any(feature = "runtime-tokio", feature = "runtime-smol")))]
+    None
+}
+
+/// Wait for `future`, but at most `duration`.
+/// Returns `None` if the time elapsed first.
+pub(crate) async fn timeout<T>(
+    runtime: &dyn Runtime,
+    duration: Duration,
+    future: impl Future<Output = T>,
+) -> Option<T> {
+    let mut sleep = runtime.sleep(duration);
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(value) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(value));
+        }
+        sleep.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+/// Runs background tasks on the current tokio runtime.
+///
+/// # Panics
+/// [`Runtime::spawn`] panics if called outside of a tokio runtime.
+#[cfg(feature = "runtime-tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+#[cfg(feature = "runtime-tokio")]
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: RuntimeFuture<'static>) -> Box<dyn TaskHandle> {
+        Box::new(tokio::spawn(future))
+    }
+
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+#[cfg(feature = "runtime-tokio")]
+impl TaskHandle for tokio::task::JoinHandle<()> {
+    fn abort(&self) {
+        tokio::task::JoinHandle::abort(self);
+    }
+
+    fn is_finished(&self) -> bool {
+        tokio::task::JoinHandle::is_finished(self)
+    }
+}
+
+/// Runs background tasks on the global `smol` executor.
+///
+/// If a tokio runtime context is entered when a task is spawned, the task is
+/// polled within this context, so that `reqwest` can perform I/O.
+#[cfg(feature = "runtime-smol")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SmolRuntime;
+
+#[cfg(feature = "runtime-smol")]
+impl Runtime for SmolRuntime {
+    fn spawn(&self, future: RuntimeFuture<'static>) -> Box<dyn TaskHandle> {
+        let future = WithTokioContext {
+            handle: tokio::runtime::Handle::try_current().ok(),
+            future,
+        };
+        Box::new(SmolTask(std::sync::Mutex::new(Some(smol::spawn(future)))))
+    }
+
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<'static> {
+        Box::pin(async move {
+            smol::Timer::after(duration).await;
+        })
+    }
+}
+
+/// Polls `future` within the context of a tokio runtime, if given.
+#[cfg(feature = "runtime-smol")]
+struct WithTokioContext {
+    handle: Option<tokio::runtime::Handle>,
+    future: RuntimeFuture<'static>,
+}
+
+#[cfg(feature = "runtime-smol")]
+impl Future for WithTokioContext {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        let _guard = self.handle.as_ref().map(tokio::runtime::Handle::enter);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// `smol` tasks are cancelled when dropped, so aborting drops the task.
+#[cfg(feature = "runtime-smol")]
+#[derive(Debug)]
+struct SmolTask(std::sync::Mutex<Option<smol::Task<()>>>);
+
+#[cfg(feature = "runtime-smol")]
+impl TaskHandle for SmolTask {
+    fn abort(&self) {
+        self.0.lock().expect("Non-poisoned lock").take();
+    }
+
+    fn is_finished(&self) -> bool {
+        self.0
+            .lock()
+            .expect("Non-poisoned lock")
+            .as_ref()
+            .is_none_or(smol::Task::is_finished)
+    }
+}
+
+#[cfg(all(test, any(feature = "runtime-tokio", feature = "runtime-smol")))]
+mod tests {
+    use http::header::CONTENT_TYPE;
+
+    use super::*;
+    use crate::BasicClientCredentialAuthorizerBuilder;
+
+    fn token_mock(server: &mut mockito::Server, expect: usize) -> mockito::Mock {
+        server
+            .mock("POST", "/token")
+            .with_status(200)
+            .with_header(CONTENT_TYPE.as_str(), "application/json")
+            .with_body(
+                serde_json::json!({
+                    "access_token": "tok",
+                    "token_type": "bearer",
+                    "expires_in": 2
+                })
+                .to_string(),
+            )
+            .expect(expect)
+            .create()
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    mod tokio_tests {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        use super::*;
+        use crate::Authorizer;
+
+        /// Runtime provided by the caller, delegating to tokio.
+        #[derive(Debug, Default)]
+        struct CountingRuntime {
+            spawned: Arc<AtomicUsize>,
+            sleeps: Arc<AtomicUsize>,
+        }
+
+        impl Runtime for CountingRuntime {
+            fn spawn(&self, future: RuntimeFuture<'static>) -> Box<dyn TaskHandle> {
+                self.spawned.fetch_add(1, Ordering::SeqCst);
+                Box::new(tokio::spawn(future))
+            }
+
+            fn sleep(&self, duration: Duration) -> RuntimeFuture<'static> {
+                self.sleeps.fetch_add(1, Ordering::SeqCst);
+                Box::pin(tokio::time::sleep(duration))
+            }
+        }
+
+        #[tokio::test]
+        async fn test_custom_runtime() {
+            let mut server = mockito::Server::new_async().await;
+            let mock = token_mock(&mut server, 2);
+            let runtime = CountingRuntime::default();
+            let spawned = runtime.spawned.clone();
+            let sleeps = runtime.sleeps.clone();
+
+            let authorizer = BasicClientCredentialAuthorizerBuilder::new(
+                "my-client",
+                "my-secret",
+                format!("{}/token", server.url()).parse().unwrap(),
+            )
+            .runtime(runtime)
+            .build()
+            .await
+            .unwrap();
+
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            mock.assert_async().await;
+            assert_eq!(spawned.load(Ordering::SeqCst), 1);
+            assert!(sleeps.load(Ordering::SeqCst) >= 1);
+
+            let task = authorizer.refresh_task().unwrap();
+            assert!(!task.is_finished());
+            assert!(authorizer.authorization_header().is_ok());
+        }
+    }
+
+    #[cfg(feature = "runtime-smol")]
+    #[test]
+    fn test_smol_runtime() {
+        // `reqwest` needs a tokio reactor for its I/O, everything else runs on smol.
+        // The refresh task inherits the context entered here.
+        let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = tokio_runtime.enter();
+
+        let mut server = mockito::Server::new();
+        let mock = token_mock(&mut server, 2);
+
+        smol::block_on(async {
+            let authorizer = BasicClientCredentialAuthorizerBuilder::new(
+                "my-client",
+                "my-secret",
+                format!("{}/token", server.url()).parse().unwrap(),
+            )
+            .runtime(SmolRuntime)
+            .build()
+            .await
+            .unwrap();
+
+            smol::Timer::after(Duration::from_millis(1500)).await;
+            mock.assert();
+            assert!(!authorizer.refresh_task().unwrap().is_finished());
+        });
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::{Duration, SystemTime};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+
+/// Claims of a validated token, inserted into the request extensions by
+/// [`BearerAuthLayer`](super::BearerAuthLayer).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Claims(Map<String, Value>);
+
+impl Claims {
+    /// Create claims from a JSON object.
+    #[must_use]
+    pub fn new(claims: Map<String, Value>) -> Self {
+        Self(claims)
+    }
+
+    /// Returns the claim `name`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// Returns the claim `name` if it is a string.
+    #[must_use]
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(Value::as_str)
+    }
+
+    /// The subject (`sub`) of the token.
+    #[must_use]
+    pub fn subject(&self) -> Option<&str> {
+        self.get_str("sub")
+    }
+
+    /// The issuer (`iss`) of the token.
+    #[must_use]
+    pub fn issuer(&self) -> Option<&str> {
+        self.get_str("iss")
+    }
+
+    /// The client the token was issued to, from the `client_id` or `azp` claim.
+    #[must_use]
+    pub fn client_id(&self) -> Option<&str> {
+        self.get_str("client_id").or_else(|| self.get_str("azp"))
+    }
+
+    /// The expiry (`exp`) of the token.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        let exp = self.get("exp")?.as_u64()?;
+        SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(exp))
+    }
+
+    /// The scopes of the token, from the space-separated `scope` claim or the `scp` array.
+    #[must_use]
+    pub fn scopes(&self) -> Vec<&str> {
+        if let Some(scope) = self.get_str("scope") {
+            return scope.split_whitespace().collect();
+        }
+        match self.get("scp") {
+            Some(Value::Array(scopes)) => scopes.iter().filter_map(Value::as_str).collect(),
+            Some(Value::String(scopes)) => scopes.split_whitespace().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the token has `scope`, see [`Claims::scopes`].
+    #[must_use]
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().contains(&scope)
+    }
+
+    /// Deserialize the claims into a custom type.
+    ///
+    /// # Errors
+    /// Fails with [`Error::InvalidToken`] if the claims do not match `T`.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_value(Value::Object(self.0.clone()))
+            .map_err(|e| Error::InvalidToken(e.to_string()))
+    }
+
+    /// The claims as JSON object.
+    #[must_use]
+    pub fn as_map(&self) -> &Map<String, Value> {
+        &self.0
+    }
+}
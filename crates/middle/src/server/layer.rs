@@ -0,0 +1,291 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http::{HeaderValue, StatusCode, header};
+
+use super::TokenValidator;
+use crate::error::Error;
+
+/// [`tower_layer::Layer`] authenticating requests with the bearer token in their
+/// `Authorization` header, for `axum` and `tonic` servers.
+///
+/// Tokens are validated with a [`TokenValidator`], for example a
+/// [`JwtValidator`](super::JwtValidator) or an
+/// [`IntrospectionValidator`](super::IntrospectionValidator). For valid tokens, the [`Claims`](super::Claims)
+/// are inserted into the request extensions before the request is passed on. Other
+/// requests are rejected:
+/// * HTTP requests with `401 Unauthorized` and a `WWW-Authenticate` header, or with
+///   `503 Service Unavailable` if the token could not be validated, for example because
+///   the keys could not be fetched.
+/// * gRPC requests with status `UNAUTHENTICATED` or `UNAVAILABLE` respectively.
+///
+/// Handlers read the claims from the extensions, for example with
+/// `axum::Extension<Claims>` or `tonic::Request::extensions`:
+///
+/// ```no_run
+/// # fn example(validator: middle::server::JwtValidator) {
+/// use middle::server::BearerAuthLayer;
+///
+/// let layer = BearerAuthLayer::new(validator);
+/// // tonic: `tonic::transport::Server::builder().layer(layer)`
+/// // axum: `axum::Router::new().route(...).layer(layer)`
+/// # }
+/// ```
+pub struct BearerAuthLayer<V> {
+    validator: Arc<V>,
+}
+
+impl<V: TokenValidator> BearerAuthLayer<V> {
+    /// Create a layer validating tokens with `validator`.
+    #[must_use]
+    pub fn new(validator: V) -> Self {
+        Self {
+            validator: Arc::new(validator),
+        }
+    }
+}
+
+impl<V> Clone for BearerAuthLayer<V> {
+    fn clone(&self) -> Self {
+        Self {
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+impl<V: Debug> Debug for BearerAuthLayer<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BearerAuthLayer")
+            .field("validator", &self.validator)
+            .finish()
+    }
+}
+
+impl<S, V> tower_layer::Layer<S> for BearerAuthLayer<V> {
+    type Service = BearerAuthService<S, V>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BearerAuthService {
+            inner,
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+/// Service created by [`BearerAuthLayer`].
+pub struct BearerAuthService<S, V> {
+    inner: S,
+    validator: Arc<V>,
+}
+
+impl<S: Clone, V> Clone for BearerAuthService<S, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+impl<S: Debug, V: Debug> Debug for BearerAuthService<S, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BearerAuthService")
+            .field("inner", &self.inner)
+            .field("validator", &self.validator)
+            .finish()
+    }
+}
+
+impl<S, V, ReqBody, ResBody> tower_service::Service<http::Request<ReqBody>>
+    for BearerAuthService<S, V>
+where
+    S: tower_service::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+    V: TokenValidator,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // Use the service that was polled ready, leave a fresh clone in its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let validator = self.validator.clone();
+        Box::pin(async move {
+            let grpc = is_grpc(&request);
+            let Some(token) = bearer_token(request.headers()) else {
+                return Ok(reject(grpc, None));
+            };
+            match validator.validate(&token).await {
+                Ok(claims) => {
+                    request.extensions_mut().insert(claims);
+                    inner.call(request).await
+                }
+                Err(e) => {
+                    tracing::debug!("Rejecting request: {e}");
+                    Ok(reject(grpc, Some(&e)))
+                }
+            }
+        })
+    }
+}
+
+/// Returns the token of an `Authorization: Bearer <token>` header.
+fn bearer_token(headers: &http::HeaderMap) -> Option<String> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then(|| token.to_string())
+}
+
+fn is_grpc<B>(request: &http::Request<B>) -> bool {
+    request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/grpc"))
+}
+
+/// Response for a request without a valid token. `error` is `None` if the request has no token.
+fn reject<B: Default>(grpc: bool, error: Option<&Error>) -> http::Response<B> {
+    // Only invalid tokens are the fault of the client.
+    let unavailable = error.is_some_and(|e| !matches!(e, Error::InvalidToken(_)));
+    let mut response = http::Response::new(B::default());
+    if grpc {
+        let (code, message) = match error {
+            None => ("16", "Missing bearer token"),
+            Some(_) if unavailable => ("14", "Token validation is unavailable"),
+            Some(_) => ("16", "Invalid bearer token"),
+        };
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/grpc"),
+        );
+        headers.insert("grpc-status", HeaderValue::from_static(code));
+        headers.insert("grpc-message", HeaderValue::from_static(message));
+    } else if unavailable {
+        *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    } else {
+        *response.status_mut() = StatusCode::UNAUTHORIZED;
+        // RFC 6750: no error code if the request lacks authentication information.
+        let challenge = if error.is_some() {
+            r#"Bearer error="invalid_token""#
+        } else {
+            "Bearer"
+        };
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static(challenge),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    use super::*;
+    use crate::server::{Claims, JwtValidator, jwt::tests};
+
+    /// Service responding with the subject of the claims.
+    #[derive(Debug, Clone)]
+    struct Echo;
+
+    impl Service<http::Request<()>> for Echo {
+        type Response = http::Response<String>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<()>) -> Self::Future {
+            let claims = request.extensions().get::<Claims>().unwrap();
+            std::future::ready(Ok(http::Response::new(
+                claims.subject().unwrap().to_string(),
+            )))
+        }
+    }
+
+    fn request(authorization: Option<&str>, grpc: bool) -> http::Request<()> {
+        let mut request = http::Request::builder();
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        if grpc {
+            request = request.header(header::CONTENT_TYPE, "application/grpc+proto");
+        }
+        request.body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bearer_auth_layer() {
+        let (server, _) = tests::jwks_server().await;
+        let mut service =
+            BearerAuthLayer::new(tests::builder(&server).build().unwrap()).layer(Echo);
+        let token = tests::sign(
+            "key-1",
+            serde_json::json!({"sub": "alice", "iss": "https://idp.example.com", "aud": "my-api"}),
+        );
+
+        let response = service
+            .call(request(Some(&format!("Bearer {token}")), false))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "alice");
+
+        let response = service.call(request(None, false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+
+        let response = service
+            .call(request(Some("Bearer invalid"), false))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="invalid_token""#
+        );
+
+        let response = service
+            .call(request(Some("Bearer invalid"), true))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["grpc-status"], "16");
+
+        // Keys cannot be fetched.
+        let mut service = BearerAuthLayer::new(
+            JwtValidator::builder("http://127.0.0.1:1/jwks".parse().unwrap())
+                .algorithms(&[jsonwebtoken::Algorithm::EdDSA])
+                .build()
+                .unwrap(),
+        )
+        .layer(Echo);
+        let response = service
+            .call(request(Some(&format!("Bearer {token}")), false))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,50 @@
+//! Validation of incoming bearer tokens, enabled with the `server` feature.
+//!
+//! * [`JwtValidator`]: validates JWTs against the keys of a JWKS endpoint, checking
+//!   signature, algorithm, issuer, audience and expiry.
+//! * [`IntrospectionValidator`]: validates opaque tokens with the token introspection
+//!   endpoint (RFC 7662), caching the results.
+//! * [`BearerAuthLayer`]: `tower` layer for `axum` and `tonic` servers that validates
+//!   the `Authorization` header with a [`TokenValidator`] and inserts the validated
+//!   [`Claims`] into the request extensions.
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use crate::error::Result;
+
+mod claims;
+mod introspection;
+mod jwt;
+mod layer;
+
+pub use claims::Claims;
+pub use introspection::{IntrospectionValidator, IntrospectionValidatorBuilder};
+pub use jsonwebtoken::Algorithm;
+pub use jwt::{JwtValidator, JwtValidatorBuilder};
+pub use layer::{BearerAuthLayer, BearerAuthService};
+
+/// Boxed future returned by [`TokenValidator::validate`].
+pub type ValidationFuture<'a> = Pin<Box<dyn Future<Output = Result<Claims>> + Send + 'a>>;
+
+/// Validates bearer tokens of incoming requests.
+pub trait TokenValidator: Send + Sync + 'static {
+    /// Validate `token`, passed without the `Bearer ` prefix, and return its claims.
+    ///
+    /// # Errors
+    /// - Fails with [`Error::InvalidToken`](crate::Error::InvalidToken) if the token is
+    ///   not valid.
+    /// - Fails with another error if the token could not be validated, for example
+    ///   because the keys could not be fetched.
+    fn validate<'a>(&'a self, token: &'a str) -> ValidationFuture<'a>;
+}
+
+impl<T: TokenValidator + ?Sized> TokenValidator for Arc<T> {
+    fn validate<'a>(&'a self, token: &'a str) -> ValidationFuture<'a> {
+        (**self).validate(token)
+    }
+}
+
+impl<T: TokenValidator + ?Sized> TokenValidator for Box<T> {
+    fn validate<'a>(&'a self, token: &'a str) -> ValidationFuture<'a> {
+        (**self).validate(token)
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use http::HeaderValue;
+
+use crate::{
+    Authorizer, AuthorizerHealth, CredentialLocation, HealthStatus,
+    error::{Error, Result},
+};
+
+/// Authorizer returning a fixed header value, counting how often it is used.
+///
+/// Clones share the counter.
+#[derive(Debug, Clone)]
+pub struct StaticAuthorizer {
+    header: Arc<HeaderValue>,
+    location: CredentialLocation,
+    calls: Arc<AtomicUsize>,
+}
+
+impl StaticAuthorizer {
+    /// Create an authorizer returning `value` as the `Authorization` header.
+    ///
+    /// # Panics
+    /// Panics if `value` is not a valid header value.
+    #[must_use]
+    pub fn new(value: &str) -> Self {
+        let mut header = HeaderValue::from_str(value).expect("Valid header value");
+        header.set_sensitive(true);
+        Self {
+            header: Arc::new(header),
+            location: CredentialLocation::default(),
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Create an authorizer returning `Bearer <token>` as the `Authorization` header.
+    ///
+    /// # Panics
+    /// Panics if `token` is not a valid header value.
+    #[must_use]
+    pub fn bearer(token: &str) -> Self {
+        Self::new(&format!("Bearer {token}"))
+    }
+
+    /// Place the value at `location` instead of the `Authorization` header.
+    #[must_use]
+    pub fn with_location(mut self, location: CredentialLocation) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Number of times the header was requested from this authorizer or its clones.
+    #[must_use]
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl Authorizer for StaticAuthorizer {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(self.header.clone())
+    }
+
+    fn credential_location(&self) -> CredentialLocation {
+        self.location.clone()
+    }
+}
+
+/// Authorizer that always fails with the given error and reports itself as unhealthy.
+#[derive(Debug, Clone)]
+pub struct FailingAuthorizer {
+    error: Error,
+}
+
+impl FailingAuthorizer {
+    /// Create an authorizer failing with `error`.
+    #[must_use]
+    pub fn new(error: Error) -> Self {
+        Self { error }
+    }
+}
+
+impl Default for FailingAuthorizer {
+    /// Fails with [`Error::TokenExpired`].
+    fn default() -> Self {
+        Self::new(Error::TokenExpired)
+    }
+}
+
+impl Authorizer for FailingAuthorizer {
+    fn authorization_header(&self) -> Result<Arc<HeaderValue>> {
+        Err(self.error.clone())
+    }
+
+    fn health(&self) -> AuthorizerHealth {
+        AuthorizerHealth::new(HealthStatus::Unhealthy {
+            error: self.error.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpClient;
+
+    #[tokio::test]
+    async fn test_static_and_failing_authorizers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/get")
+            .match_header("authorization", "Bearer test")
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let authorizer = StaticAuthorizer::bearer("test");
+        let client = HttpClient::new(authorizer.clone());
+        client
+            .get(format!("{}/get", server.url()))
+            .send()
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        assert_eq!(authorizer.calls(), 1);
+
+        let client = HttpClient::new(FailingAuthorizer::new(Error::NoAuthorization));
+        assert!(matches!(
+            client.get(server.url()).send().await,
+            Err(Error::NoAuthorization)
+        ));
+        assert!(!FailingAuthorizer::default().health().is_ready());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::Clock;
+
+/// [`Clock`] that only advances when told to, to test token expiry deterministically.
+///
+/// Clones share the same time. [`Clock::system_time`] advances together with
+/// [`Clock::now`].
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    start: (Instant, SystemTime),
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// Create a clock starting at the current instant.
+    #[must_use]
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            start: (now, SystemTime::now()),
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn system_time(&self) -> SystemTime {
+        let (start, start_system_time) = self.start;
+        start_system_time + (self.now() - start)
+    }
+}
+
+#[cfg(all(test, feature = "client-credentials"))]
+mod tests {
+    use super::*;
+    use crate::{
+        Authorizer, BasicClientCredentialAuthorizerBuilder, Error, testing::MockTokenServer,
+    };
+
+    #[tokio::test]
+    async fn test_manual_clock_expires_token() {
+        let server = MockTokenServer::start();
+        server.set_expires_in(Some(Duration::from_secs(60)));
+        let clock = ManualClock::new();
+
+        let authorizer = BasicClientCredentialAuthorizerBuilder::new(
+            "my-client",
+            "my-secret",
+            server.token_url(),
+        )
+        .disable_refresh()
+        .clock(clock.clone())
+        .build()
+        .await
+        .unwrap();
+
+        clock.advance(Duration::from_secs(59));
+        assert!(authorizer.authorization_header().is_ok());
+        assert!(authorizer.health().is_healthy());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            authorizer.authorization_header(),
+            Err(Error::TokenExpired)
+        ));
+        assert!(!authorizer.health().is_ready());
+        server.assert_requests(1);
+    }
+}
@@ -0,0 +1,14 @@
+//! Utilities for testing code that uses this crate, enabled with the `testing` feature.
+//!
+//! * [`MockTokenServer`]: local `OAuth2` token endpoint with configurable token
+//!   lifetimes, failure injection and request recording.
+//! * [`StaticAuthorizer`] and [`FailingAuthorizer`]: authorizers with fixed behavior
+//!   for unit tests of code that takes an [`Authorizer`](crate::Authorizer).
+//! * [`ManualClock`]: [`Clock`](crate::Clock) to control token expiry.
+mod authorizers;
+mod clock;
+mod token_server;
+
+pub use authorizers::{FailingAuthorizer, StaticAuthorizer};
+pub use clock::ManualClock;
+pub use token_server::{MockTokenServer, TokenFailure, TokenRequest};
@@ -0,0 +1,431 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use base64::Engine;
+
+/// Failure injected into the responses of a [`MockTokenServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TokenFailure {
+    /// Respond with the given HTTP status code and an empty body, for example 503.
+    Status(u16),
+    /// Respond with `401 Unauthorized` and the `OAuth2` error `invalid_client`.
+    InvalidClient,
+    /// Wait for the given duration, then close the connection without responding.
+    /// Configure a timeout on the HTTP client to observe this as a timeout.
+    Timeout(Duration),
+}
+
+/// Token request received by a [`MockTokenServer`].
+#[derive(Clone, PartialEq, Eq, veil::Redact)]
+#[non_exhaustive]
+pub struct TokenRequest {
+    /// Client id from the `Authorization: Basic` header or the `client_id` parameter.
+    pub client_id: Option<String>,
+    /// Client secret from the `Authorization: Basic` header or the `client_secret` parameter.
+    #[redact]
+    pub client_secret: Option<String>,
+    /// The `grant_type` parameter.
+    pub grant_type: Option<String>,
+    /// Scopes from the space-separated `scope` parameter.
+    pub scopes: Vec<String>,
+    /// All other form parameters of the request.
+    pub extra_params: HashMap<String, String>,
+}
+
+/// Local `OAuth2` token endpoint for tests.
+///
+/// Listens on a random port of `127.0.0.1` and answers `POST /token` with a new
+/// bearer token per request (`token-1`, `token-2`, ...). The server runs on
+/// background threads, so it can be used with any async runtime and with
+/// blocking clients. It stops when dropped.
+///
+/// ```no_run
+/// # async fn example() {
+/// use middle::{Authorizer, BasicClientCredentialAuthorizerBuilder, testing::MockTokenServer};
+///
+/// let server = MockTokenServer::start();
+/// let authorizer =
+///     BasicClientCredentialAuthorizerBuilder::new("my-client", "my-secret", server.token_url())
+///         .add_scope("read")
+///         .build()
+///         .await
+///         .unwrap();
+/// assert_eq!(
+///     authorizer.authorization_header().unwrap().to_str().unwrap(),
+///     "Bearer token-1"
+/// );
+/// server.assert_requests(1);
+/// server.assert_scopes(&["read"]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MockTokenServer {
+    addr: SocketAddr,
+    url: url::Url,
+    state: Arc<ServerState>,
+}
+
+#[derive(Debug)]
+struct ServerState {
+    stopped: AtomicBool,
+    expires_in: Mutex<Option<Duration>>,
+    failures: Mutex<VecDeque<TokenFailure>>,
+    requests: Mutex<Vec<TokenRequest>>,
+    issued: Mutex<u64>,
+}
+
+impl MockTokenServer {
+    /// Start a new server issuing tokens that expire after one hour.
+    ///
+    /// # Panics
+    /// Panics if no local port can be bound.
+    #[must_use]
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock token server");
+        let addr = listener
+            .local_addr()
+            .expect("Bound listener has an address");
+        let state = Arc::new(ServerState {
+            stopped: AtomicBool::new(false),
+            expires_in: Mutex::new(Some(Duration::from_secs(3600))),
+            failures: Mutex::new(VecDeque::new()),
+            requests: Mutex::new(Vec::new()),
+            issued: Mutex::new(0),
+        });
+
+        let accept_state = state.clone();
+        std::thread::Builder::new()
+            .name("middle-mock-token-server".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if accept_state.stopped.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let state = accept_state.clone();
+                    std::thread::spawn(move || state.handle(stream));
+                }
+            })
+            .expect("Failed to spawn mock token server");
+
+        let url = format!("http://{addr}/")
+            .parse()
+            .expect("Valid socket address URL");
+        Self { addr, url, state }
+    }
+
+    /// Base URL of the server, for example `http://127.0.0.1:12345/`.
+    #[must_use]
+    pub fn url(&self) -> &url::Url {
+        &self.url
+    }
+
+    /// URL of the token endpoint, to pass to the authorizer.
+    #[must_use]
+    pub fn token_url(&self) -> url::Url {
+        let mut url = self.url.clone();
+        url.set_path("/token");
+        url
+    }
+
+    /// Set the lifetime (`expires_in`) of tokens issued from now on.
+    /// `None` omits `expires_in` from the response, so the token never expires.
+    pub fn set_expires_in(&self, expires_in: Option<Duration>) {
+        *lock(&self.state.expires_in) = expires_in;
+    }
+
+    /// Answer the next token request with `failure` instead of a token.
+    /// Can be called multiple times to queue failures for consecutive requests.
+    pub fn fail_next(&self, failure: TokenFailure) {
+        lock(&self.state.failures).push_back(failure);
+    }
+
+    /// Answer the next `count` token requests with `failure`.
+    pub fn fail_next_n(&self, count: usize, failure: &TokenFailure) {
+        let mut failures = lock(&self.state.failures);
+        failures.extend(std::iter::repeat_n(failure.clone(), count));
+    }
+
+    /// All token requests received so far, including failed ones, in order.
+    #[must_use]
+    pub fn requests(&self) -> Vec<TokenRequest> {
+        lock(&self.state.requests).clone()
+    }
+
+    /// Number of token requests received so far.
+    #[must_use]
+    pub fn request_count(&self) -> usize {
+        lock(&self.state.requests).len()
+    }
+
+    /// Assert that exactly `expected` token requests were received.
+    ///
+    /// # Panics
+    /// Panics if the number of requests differs.
+    pub fn assert_requests(&self, expected: usize) {
+        let count = self.request_count();
+        assert_eq!(
+            count, expected,
+            "Expected {expected} token requests, received {count}"
+        );
+    }
+
+    /// Assert that the last token request asked for exactly `scopes`, in any order.
+    ///
+    /// # Panics
+    /// Panics if no request was received or the scopes differ.
+    pub fn assert_scopes(&self, scopes: &[&str]) {
+        let mut actual = self.last_request().scopes;
+        actual.sort_unstable();
+        let mut expected = scopes.iter().map(ToString::to_string).collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(actual, expected, "Unexpected scopes in token request");
+    }
+
+    /// Assert that the last token request contained the parameter `name` with `value`.
+    ///
+    /// # Panics
+    /// Panics if no request was received or the parameter is missing or differs.
+    pub fn assert_extra_param(&self, name: &str, value: &str) {
+        let request = self.last_request();
+        assert_eq!(
+            request.extra_params.get(name).map(String::as_str),
+            Some(value),
+            "Unexpected value of parameter `{name}` in token request"
+        );
+    }
+
+    fn last_request(&self) -> TokenRequest {
+        lock(&self.state.requests)
+            .last()
+            .cloned()
+            .expect("No token request received")
+    }
+}
+
+impl Drop for MockTokenServer {
+    fn drop(&mut self) {
+        self.state.stopped.store(true, Ordering::SeqCst);
+        // Wake up the accept loop so that it observes `stopped`.
+        TcpStream::connect(self.addr).ok();
+    }
+}
+
+impl ServerState {
+    fn handle(&self, stream: TcpStream) {
+        if let Err(e) = self.try_handle(stream) {
+            tracing::debug!("Mock token server failed to handle request: {e}");
+        }
+    }
+
+    fn try_handle(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        let content_length = headers
+            .get("content-length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let mut parts = request_line.split_whitespace();
+        if parts.next() != Some("POST") || parts.next() != Some("/token") {
+            return respond(&mut stream, 404, "");
+        }
+
+        lock(&self.requests).push(parse_request(&headers, &body));
+
+        let failure = lock(&self.failures).pop_front();
+        match failure {
+            Some(TokenFailure::Status(status)) => respond(&mut stream, status, ""),
+            Some(TokenFailure::InvalidClient) => respond(
+                &mut stream,
+                401,
+                &serde_json::json!({
+                    "error": "invalid_client",
+                    "error_description": "Client authentication failed"
+                })
+                .to_string(),
+            ),
+            Some(TokenFailure::Timeout(duration)) => {
+                std::thread::sleep(duration);
+                Ok(())
+            }
+            None => {
+                let token = {
+                    let mut issued = lock(&self.issued);
+                    *issued += 1;
+                    format!("token-{issued}")
+                };
+                let mut response = serde_json::json!({
+                    "access_token": token,
+                    "token_type": "bearer",
+                });
+                if let Some(expires_in) = *lock(&self.expires_in) {
+                    response["expires_in"] = expires_in.as_secs().into();
+                }
+                respond(&mut stream, 200, &response.to_string())
+            }
+        }
+    }
+}
+
+/// Lock `mutex`, ignoring poisoning: a failed assertion in one test thread must
+/// not break the server for the remaining requests.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &str) -> std::io::Result<()> {
+    let reason = http::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn parse_request(headers: &HashMap<String, String>, body: &[u8]) -> TokenRequest {
+    let mut params = url::form_urlencoded::parse(body)
+        .into_owned()
+        .collect::<HashMap<_, _>>();
+
+    // `oauth2` sends the credentials form-urlencoded in the basic auth header by default.
+    let basic = headers
+        .get("authorization")
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|credentials| {
+            base64::engine::general_purpose::STANDARD
+                .decode(credentials)
+                .ok()
+        })
+        .and_then(|credentials| String::from_utf8(credentials).ok())
+        .and_then(|credentials| {
+            let (id, secret) = credentials.split_once(':')?;
+            Some((form_decode(id), form_decode(secret)))
+        });
+    let (client_id, client_secret) = match basic {
+        Some((id, secret)) => (Some(id), Some(secret)),
+        None => (params.remove("client_id"), params.remove("client_secret")),
+    };
+
+    TokenRequest {
+        client_id,
+        client_secret,
+        grant_type: params.remove("grant_type"),
+        scopes: params
+            .remove("scope")
+            .map(|scope| scope.split_whitespace().map(ToString::to_string).collect())
+            .unwrap_or_default(),
+        extra_params: params,
+    }
+}
+
+fn form_decode(value: &str) -> String {
+    url::form_urlencoded::parse(value.as_bytes())
+        .next()
+        .map(|(decoded, _)| decoded.into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, feature = "client-credentials"))]
+mod tests {
+    use super::*;
+    use crate::{Authorizer, BasicClientCredentialAuthorizerBuilder, Error};
+
+    #[tokio::test]
+    async fn test_mock_token_server() {
+        let server = MockTokenServer::start();
+        let authorizer = BasicClientCredentialAuthorizerBuilder::new(
+            "my client",
+            "my-secret",
+            server.token_url(),
+        )
+        .add_scopes(&["read", "write"])
+        .add_extra_param("audience", "my-api")
+        .build()
+        .await
+        .unwrap();
+        assert_eq!(
+            authorizer.authorization_header().unwrap().to_str().unwrap(),
+            "Bearer token-1"
+        );
+
+        server.assert_requests(1);
+        server.assert_scopes(&["write", "read"]);
+        server.assert_extra_param("audience", "my-api");
+        let request = &server.requests()[0];
+        assert_eq!(request.client_id.as_deref(), Some("my client"));
+        assert_eq!(request.client_secret.as_deref(), Some("my-secret"));
+        assert_eq!(request.grant_type.as_deref(), Some("client_credentials"));
+        assert!(!format!("{request:?}").contains("my-secret"));
+
+        authorizer.refresh_now().await.unwrap();
+        assert_eq!(
+            authorizer.authorization_header().unwrap().to_str().unwrap(),
+            "Bearer token-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_token_server_failures() {
+        let server = MockTokenServer::start();
+        let builder = || {
+            BasicClientCredentialAuthorizerBuilder::new(
+                "my-client",
+                "my-secret",
+                server.token_url(),
+            )
+            .set_max_retries(0)
+            .set_http_client(
+                reqwest::Client::builder()
+                    .timeout(Duration::from_millis(100))
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        server.fail_next(TokenFailure::InvalidClient);
+        let error = builder().build().await.unwrap_err();
+        assert!(matches!(&error, Error::OAuth2RequestFailed(e) if e.contains("invalid_client")));
+
+        server.fail_next(TokenFailure::Timeout(Duration::from_millis(500)));
+        assert!(builder().build().await.is_err());
+
+        // Retries recover from transient failures.
+        server.fail_next_n(2, &TokenFailure::Status(503));
+        server.set_expires_in(None);
+        let authorizer = builder().set_max_retries(2).build().await.unwrap();
+        assert!(authorizer.refresh_task().is_none());
+        server.assert_requests(5);
+    }
+}
@@ -25,9 +25,9 @@ async fn main() {
 
     // Generate a new reqwest Client and wrap it with `HttpClient`.
     let reqwest_client = Client::new();
-    let client = middle::HttpClient::new(authorizer).set_client(reqwest_client);
+    let client = middle::HttpClient::from((reqwest_client, authorizer));
 
     // Start using the client - the authorization header is automatically added.
-    let request = client.get("https://api.example.com/data").unwrap();
+    let request = client.get("https://api.example.com/data");
     let _response = request.send().await.unwrap();
 }